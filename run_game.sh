@@ -3,5 +3,7 @@
 set -e
 
 cargo build
+# Pick a bot with --strategy move_random_and_back|fixed_pattern|example, e.g.
+#./halite --replay-directory replays/ -vvv "./target/debug/my_bot --strategy example" "./target/debug/my_bot --strategy fixed_pattern"
 #./halite --replay-directory replays/ -vvv --width 32 --height 32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/my_bot"
 ./halite --replay-directory replays/ -vvv "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/my_bot"
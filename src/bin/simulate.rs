@@ -0,0 +1,108 @@
+extern crate my_bot;
+
+use my_bot::hlt::PlayerId;
+use my_bot::simulator;
+use my_bot::simulator::replay::Replay;
+use my_bot::simulator::Simulator;
+use my_bot::strategy;
+use my_bot::strategy::Strategy;
+use std::env;
+use std::io::Empty;
+use std::path::PathBuf;
+use std::process;
+
+/// Usage: `simulate [games] [--players n] [--size n] [--seed n] [--strategy name]... [--replays directory]`.
+/// Plays the games in-process, one after the other, and prints who won each of them.
+/// Give `--strategy` once per player, by default everyone plays the default strategy.
+fn main() {
+    let mut games: u64 = 10;
+    let mut num_players = 2;
+    let mut size = 32;
+    let mut first_seed: u64 = 0;
+    let mut strategy_names: Vec<String> = Vec::new();
+    let mut replay_dir: Option<PathBuf> = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--players" => num_players = parse_next(&mut args, &arg),
+            "--size" => size = parse_next(&mut args, &arg),
+            "--seed" => first_seed = parse_next(&mut args, &arg),
+            "--strategy" => strategy_names.push(parse_next(&mut args, &arg)),
+            "--replays" => replay_dir = Some(parse_next(&mut args, &arg)),
+            _ => games = arg.parse().unwrap_or_else(|_| usage(&format!("Not a number of games: {}", arg))),
+        }
+    }
+    if strategy_names.is_empty() {
+        strategy_names.push(strategy::STRATEGY_NAMES[0].to_string());
+    }
+    while strategy_names.len() < num_players {
+        let last = strategy_names[strategy_names.len() - 1].clone();
+        strategy_names.push(last);
+    }
+    if strategy_names.len() > num_players {
+        usage("More strategies than players.");
+    }
+
+    let mut wins = vec![0; num_players];
+    for seed in (0..games).map(|game| first_seed.wrapping_add(game)) {
+        let mut strategies: Vec<Box<dyn Strategy<Empty>>> = strategy_names.iter()
+            .enumerate()
+            .map(|(index, name)| {
+                // Distinct for every player, even for seeds near u64::MAX.
+                let bot_seed = seed.wrapping_mul(num_players as u64).wrapping_add(index as u64);
+                strategy::by_name(name, bot_seed)
+                    .unwrap_or_else(|| usage(&format!("Unknown strategy '{}'. Known are: {}",
+                        name, strategy::STRATEGY_NAMES.join(", "))))
+            })
+            .collect();
+
+        let constants = simulator::default_constants(size, size);
+        let mut simulator = Simulator::new(constants, num_players, size, size, seed);
+        for (index, strategy) in strategies.iter_mut().enumerate() {
+            strategy.init(&simulator.game_for(PlayerId(index)));
+        }
+        let names: Vec<&str> = strategy_names.iter().map(|name| &name[..]).collect();
+        let mut replay = replay_dir.as_ref().map(|_| Replay::new(&simulator, &names));
+
+        simulator.play_out(replay.as_mut(), |simulator, player_id| {
+            strategies[player_id.0].next_turn(&simulator.game_for(player_id))
+        });
+
+        if let (Some(replay), Some(dir)) = (replay, &replay_dir) {
+            let path = dir.join(format!("replay-{}.hlt", seed));
+            if let Err(error) = replay.save(&path) {
+                eprintln!("Couldn't save {}: {}", path.display(), error);
+            }
+        }
+
+        let rankings = simulator.rankings();
+        wins[rankings[0].0] += 1;
+        let halite: Vec<String> = simulator.players.iter()
+            .map(|player| player.halite.to_string())
+            .collect();
+        println!("Seed {}: player {} won, halite {}", seed, rankings[0].0, halite.join(" / "));
+        for (index, elimination) in simulator.eliminations.iter().enumerate() {
+            if let Some(reason) = elimination {
+                println!("  Player {} was eliminated: {}", index, reason);
+            }
+        }
+    }
+
+    for (index, name) in strategy_names.iter().enumerate() {
+        println!("Player {} ({}) won {} of {} games.", index, name, wins[index], games);
+    }
+}
+
+fn parse_next<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, option: &str) -> T {
+    match args.next().map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        _ => usage(&format!("{} needs a value.", option)),
+    }
+}
+
+fn usage(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("Usage: simulate [games] [--players n] [--size n] [--seed n] [--strategy name]... [--replays directory]");
+    process::exit(2);
+}
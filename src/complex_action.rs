@@ -1,4 +1,5 @@
 use hlt::position::Position;
+use hlt::ShipId;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum ComplexAction {
@@ -6,8 +7,23 @@ pub enum ComplexAction {
     //Dropoff, // Maybe the dropoff should play traffic control?
     Navigate(Position), // Only move
     NavigateCollect(Position), // Collect on the way
-    //Attack,  // Don't fear collision
+    Attack(ShipId),  // Don't fear collision
 
-    //TODO Dodge(original Position), // Dodge another ship
-    //DodgeCollect(original Position)
+    Dodge(Position, Position), // Step aside from the blocked cell, then Navigate once it's free or passed
+    DodgeCollect(Position, Position), // Step aside, then NavigateCollect
+}
+
+impl ComplexAction {
+    /// The same action, after stepping aside for a blocker on that cell.
+    pub fn dodge(self, blocked: Position) -> ComplexAction {
+        match self {
+            ComplexAction::Navigate(destination) => ComplexAction::Dodge(destination, blocked),
+            ComplexAction::NavigateCollect(destination) => ComplexAction::DodgeCollect(destination, blocked),
+            action => action,
+        }
+    }
+
+    pub fn is_dodge(&self) -> bool {
+        matches!(self, ComplexAction::Dodge(..) | ComplexAction::DodgeCollect(..))
+    }
 }
@@ -0,0 +1,185 @@
+use hlt::direction::Direction;
+use hlt::game::Game;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::PlayerId;
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+/// How many moves it takes from every cell to the nearest of several
+/// sources on the wrapping map, e.g. to the nearest own dropoff.
+/// Computed once per turn with a breadth-first search,
+/// then every query is a lookup.
+pub struct DistanceField {
+    distances: Vec<Option<usize>>,
+    nearest: Vec<Option<CellIndex>>,
+}
+
+impl DistanceField {
+    /// Searches from all sources at once. Obstacles are never entered,
+    /// but a source that is an obstacle still has distance 0.
+    pub fn new<F>(game_map: &GameMap, sources: &[CellIndex], is_obstacle: F) -> DistanceField
+        where F: Fn(CellIndex) -> bool
+    {
+        let size = game_map.width * game_map.height;
+        let mut distances: Vec<Option<usize>> = vec![None; size];
+        let mut nearest: Vec<Option<CellIndex>> = vec![None; size];
+        let mut queue = VecDeque::with_capacity(size);
+
+        for &source in sources {
+            if distances[source.0].is_none() {
+                distances[source.0] = Some(0);
+                nearest[source.0] = Some(source);
+                queue.push_back(source);
+            }
+        }
+
+        while let Some(index) = queue.pop_front() {
+            let next_distance = distances[index.0].map(|distance| distance + 1);
+            for &neighbour in &game_map.neighbours(index) {
+                if distances[neighbour.0].is_none() && !is_obstacle(neighbour) {
+                    distances[neighbour.0] = next_distance;
+                    nearest[neighbour.0] = nearest[index.0];
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        DistanceField { distances, nearest }
+    }
+
+    /// Distances to the shipyard and dropoffs of the player.
+    pub fn to_structures<R: BufRead, F>(game: &Game<R>, player_id: PlayerId, is_obstacle: F) -> DistanceField
+        where F: Fn(CellIndex) -> bool
+    {
+        let player = &game.players[player_id.0];
+        let mut sources = vec![game.game_map.index_of(&player.shipyard.position)];
+        for dropoff_id in &player.dropoff_ids {
+            sources.push(game.game_map.index_of(&game.dropoffs[dropoff_id].position));
+        }
+        DistanceField::new(&game.game_map, &sources, is_obstacle)
+    }
+
+    /// Distances to the shipyards and dropoffs of all other players.
+    pub fn to_enemy_structures<R: BufRead, F>(game: &Game<R>, player_id: PlayerId, is_obstacle: F) -> DistanceField
+        where F: Fn(CellIndex) -> bool
+    {
+        let mut sources = Vec::new();
+        for player in game.players.iter().filter(|player| player.id != player_id) {
+            sources.push(game.game_map.index_of(&player.shipyard.position));
+            for dropoff_id in &player.dropoff_ids {
+                sources.push(game.game_map.index_of(&game.dropoffs[dropoff_id].position));
+            }
+        }
+        DistanceField::new(&game.game_map, &sources, is_obstacle)
+    }
+
+    /// None if an obstacle is in the way or there is no source.
+    pub fn distance(&self, index: CellIndex) -> Option<usize> {
+        self.distances[index.0]
+    }
+
+    /// The source that is closest to the cell.
+    pub fn nearest_source(&self, index: CellIndex) -> Option<CellIndex> {
+        self.nearest[index.0]
+    }
+
+    /// A direction that gets one step closer to the nearest source.
+    /// `Still` on a source, None if no source can be reached.
+    pub fn step_towards_source(&self, game_map: &GameMap, index: CellIndex) -> Option<Direction> {
+        let distance = self.distance(index)?;
+        if distance == 0 {
+            return Some(Direction::Still);
+        }
+        Direction::get_all_cardinals().into_iter()
+            .find(|&direction| self.distance(game_map.neighbour(index, direction)) == Some(distance - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::position::Position;
+
+    fn empty_map(width: usize, height: usize) -> GameMap {
+        GameMap::new(width, height, &vec![0; width * height])
+    }
+
+    fn at(game_map: &GameMap, x: i32, y: i32) -> CellIndex {
+        game_map.index_of(&Position { x, y })
+    }
+
+    #[test]
+    fn distances_wrap_around_the_edges() {
+        let game_map = empty_map(8, 6);
+        let field = DistanceField::new(&game_map, &[at(&game_map, 0, 0)], |_| false);
+        assert_eq!(field.distance(at(&game_map, 0, 0)), Some(0));
+        assert_eq!(field.distance(at(&game_map, 7, 0)), Some(1));
+        assert_eq!(field.distance(at(&game_map, 0, 5)), Some(1));
+        assert_eq!(field.distance(at(&game_map, 4, 3)), Some(7));
+        for index in game_map.indices() {
+            let position = game_map.position_of(index);
+            assert_eq!(field.distance(index), Some(game_map.calculate_distance(&Position { x: 0, y: 0 }, &position)));
+        }
+    }
+
+    #[test]
+    fn every_cell_knows_its_nearest_source() {
+        let game_map = empty_map(10, 10);
+        let (left, right) = (at(&game_map, 1, 5), at(&game_map, 6, 5));
+        let field = DistanceField::new(&game_map, &[left, right], |_| false);
+        assert_eq!(field.nearest_source(at(&game_map, 2, 2)), Some(left));
+        assert_eq!(field.distance(at(&game_map, 2, 2)), Some(4));
+        assert_eq!(field.nearest_source(at(&game_map, 8, 5)), Some(right));
+        // Over the edge, the left one is closer.
+        assert_eq!(field.nearest_source(at(&game_map, 9, 5)), Some(left));
+        assert_eq!(field.distance(at(&game_map, 9, 5)), Some(2));
+    }
+
+    #[test]
+    fn obstacles_are_walked_around() {
+        // A wall in column 2 with a gap at y = 4, and one in column 8 without a gap.
+        let game_map = empty_map(11, 11);
+        let wall = |index: CellIndex| {
+            let position = game_map.position_of(index);
+            (position.x == 2 && position.y != 4) || position.x == 8
+        };
+        let source = at(&game_map, 0, 0);
+        let field = DistanceField::new(&game_map, &[source], wall);
+        assert_eq!(field.distance(at(&game_map, 2, 0)), None);
+        assert_eq!(field.distance(at(&game_map, 2, 4)), Some(6));
+        assert_eq!(field.distance(at(&game_map, 3, 0)), Some(4 + 3 + 4));
+        assert_eq!(field.distance(at(&game_map, 9, 0)), Some(2));
+
+        // Fully walled in, the source itself still counts.
+        let field = DistanceField::new(&game_map, &[source], |index| index != source);
+        assert_eq!(field.distance(source), Some(0));
+        assert_eq!(field.distance(at(&game_map, 1, 0)), None);
+        assert_eq!(field.step_towards_source(&game_map, at(&game_map, 1, 0)), None);
+    }
+
+    #[test]
+    fn steps_lead_to_the_source() {
+        let game_map = empty_map(9, 7);
+        let source = at(&game_map, 4, 3);
+        let field = DistanceField::new(&game_map, &[source], |_| false);
+        assert_eq!(field.step_towards_source(&game_map, source), Some(Direction::Still));
+        for start in game_map.indices() {
+            let mut index = start;
+            let mut steps = 0;
+            while index != source {
+                let direction = field.step_towards_source(&game_map, index).unwrap();
+                index = game_map.neighbour(index, direction);
+                steps += 1;
+            }
+            assert_eq!(Some(steps), field.distance(start));
+        }
+    }
+
+    #[test]
+    fn no_sources_means_no_distances() {
+        let game_map = empty_map(4, 4);
+        let field = DistanceField::new(&game_map, &[], |_| false);
+        assert!(game_map.indices().all(|index| field.distance(index).is_none()));
+    }
+}
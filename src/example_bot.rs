@@ -0,0 +1,53 @@
+use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::game::Game;
+use rand::Rng;
+use rand::XorShiftRng;
+use std::io::BufRead;
+use strategy;
+use strategy::Strategy;
+
+/// The bot from the starter kit: Ships collect until the cell is
+/// almost empty or they are full, then move in a random direction.
+pub struct ExampleBot {
+    rng: XorShiftRng,
+}
+
+impl ExampleBot {
+    pub fn new(rng_seed: u64) -> ExampleBot {
+        ExampleBot { rng: strategy::seeded_rng(rng_seed) }
+    }
+}
+
+impl<R: BufRead> Strategy<R> for ExampleBot {
+    fn next_turn(&mut self, game: &Game<R>) -> Vec<Command> {
+        let me = &game.players[game.my_id.0];
+        let game_map = &game.game_map;
+
+        let mut command_queue: Vec<Command> = Vec::new();
+
+        for ship_id in &me.ship_ids {
+            let ship = &game.ships[ship_id];
+            let cell = game_map.at_entity(ship);
+
+            let command = if cell.halite < game.constants.max_halite / 10 || ship.is_full() {
+                let random_direction = Direction::get_all_cardinals()[self.rng.gen_range(0, 4)];
+                ship.move_ship(random_direction)
+            } else {
+                ship.stay_still()
+            };
+            command_queue.push(command);
+        }
+
+        let shipyard_cell = game_map.at_entity(&me.shipyard);
+
+        if game.turn_number <= 200 &&
+                me.halite >= game.constants.ship_cost &&
+                !shipyard_cell.is_occupied()
+        {
+            command_queue.push(me.shipyard.spawn());
+        }
+
+        command_queue
+    }
+}
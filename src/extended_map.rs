@@ -1,47 +1,99 @@
 use hlt::position::Position;
-use std::collections::HashSet;
+use std::io::BufRead;
+use hlt::game::Game;
 use hlt::game_map::GameMap;
+use hlt::ship::Ship;
+use distance_field::DistanceField;
+use halite_field::HaliteField;
+use inspiration_map::InspirationMap;
+use hlt::ShipId;
+use hlt::game_map::CellIndex;
+use reservation_table::Occupant;
+use reservation_table::ReservationTable;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use threat_map::ThreatMap;
+
+const NEARBY_RADIUS: usize = 3;
+const RESERVATION_TURNS: usize = 8;
 
 /// A game map with extended information and functionality.
 /// Collision Avoidance.
 pub struct ExtendedMap<'game> {
     pub game_map: &'game GameMap,
-    /// Positions where ships will be in the next turn.
-    collision_positions: HashSet<Position>,
+    /// Where ships will be in the next turns.
+    pub reservations: ReservationTable,
+    my_ships: HashSet<ShipId>,
+    /// Distances to my shipyard and dropoffs.
+    pub structure_distances: DistanceField,
+    /// The halite a ship can reach within a few moves.
+    pub nearby_halite: HaliteField,
+    /// Where my ships would be inspired.
+    pub inspiration: InspirationMap,
+    /// Where enemy ships could be in the next turn.
+    pub threats: ThreatMap,
 }
 
 impl<'game> ExtendedMap<'game> {
-    pub fn new(game_map: &'game GameMap) -> ExtendedMap {
+    /// Pass in the reservations of the previous turn, so the plans of my ships carry over.
+    pub fn new<R: BufRead>(
+        game: &'game Game<R>, reservations: Option<ReservationTable>
+    ) -> ExtendedMap<'game> {
+        let mut reservations = reservations
+            .unwrap_or_else(|| ReservationTable::new(&game.game_map, RESERVATION_TURNS));
+        let positions: HashMap<ShipId, CellIndex> = game.ships.values()
+            .map(|ship| (ship.id, game.game_map.index_of(&ship.position)))
+            .collect();
+        reservations.advance(&positions);
+
+        // Enemy ships might stay, so they keep their cells for the next turn.
+        for ship in game.ships.values() {
+            if ship.owner != game.my_id {
+                reservations.book(ship.id, positions[&ship.id], 1);
+            }
+        }
+
         ExtendedMap {
-            game_map,
-            collision_positions: HashSet::new()
+            game_map: &game.game_map,
+            reservations,
+            my_ships: game.players[game.my_id.0].ship_ids.iter().cloned().collect(),
+            structure_distances: DistanceField::to_structures(game, game.my_id, |_| false),
+            nearby_halite: HaliteField::diamond_sum(&game.game_map, NEARBY_RADIUS),
+            inspiration: InspirationMap::for_me(game),
+            threats: ThreatMap::for_me(game),
         }
     }
 
     /// Collision Avoidance.
-    /// Returns true if the position is still free. That position
-    /// will be marked as occupied.
+    /// Returns true if the position is still free in the next turn.
+    /// The ship books that position then.
     /// Should be used with
-    /// ```
-    /// if ex_map.try_reserve_cell(pos) {
+    /// ```ignore
+    /// if ex_map.can_move_safely_then_reserve(ship, pos) {
     ///     ship.move(...)
     /// }
     /// ```
-    pub fn can_move_safely_then_reserve(&mut self, position: &Position) -> bool {
-        // Don't movement to an occupied cell
-        if self.game_map.at_position(position).ship.is_some() {
-            return false;
-        }
-
-        // Try reserve position
-        if self.collision_positions.get(position).cloned().is_some() {
-            return false;
-        } else { // not occupied
-            self.collision_positions.insert(position.clone());
-            return true;
-        }
+    pub fn can_move_safely_then_reserve(&mut self, ship: &Ship, position: &Position) -> bool {
+        self.reservations.reserve(ship.id, self.game_map.index_of(position), 1)
     }
-}
 
+    /// Whether my ships may go there next turn at all, because no enemy ship
+    /// or spawn is expected. Among my ships, the `MoveResolver` decides.
+    pub fn is_open(&self, position: &Position) -> bool {
+        self.is_open_index(self.game_map.index_of(position))
+    }
 
+    /// Like `is_open`. The bookings of my ships are only plans yet.
+    pub fn is_open_index(&self, index: CellIndex) -> bool {
+        match self.reservations.occupant(index, 1) {
+            Some(Occupant::Ship(ship_id)) => self.my_ships.contains(&ship_id),
+            Some(Occupant::NewShip) => false,
+            None => true,
+        }
+    }
 
+    /// Like `can_move_safely_then_reserve`, for a ship that isn't spawned yet.
+    pub fn can_spawn_safely_then_reserve(&mut self, position: &Position) -> bool {
+        self.reservations.reserve_spawn(self.game_map.index_of(position))
+    }
+}
@@ -1,59 +1,73 @@
 use hlt::game::Game;
 use hlt::command::Command;
 use hlt::direction::Direction;
+use std::io::BufRead;
+use strategy::Strategy;
 
 
 /* To practise the API, I'll send a ship around in a loop. */
-pub fn run(mut game: Game) {
-    let mut initial_turn = true;
-    let mut programmed_commands: Vec<Command> = Vec::new();
+pub struct FixedPatternBot {
+    initial_turn: bool,
+    programmed_commands: Vec<Command>,
+}
 
-    loop {
-        game.update_frame();
+impl FixedPatternBot {
+    pub fn new() -> FixedPatternBot {
+        FixedPatternBot { initial_turn: true, programmed_commands: Vec::new() }
+    }
+}
+
+impl Default for FixedPatternBot {
+    fn default() -> FixedPatternBot {
+        FixedPatternBot::new()
+    }
+}
 
+impl<R: BufRead> Strategy<R> for FixedPatternBot {
+    fn next_turn(&mut self, game: &Game<R>) -> Vec<Command> {
         let mut command_queue: Vec<Command> = Vec::new();
         let me = &game.players[game.my_id.0];
 
         // Initial turn spawns a ship.
-        if initial_turn {
+        if self.initial_turn {
             command_queue.push(me.shipyard.spawn());
-            initial_turn = false;
+            self.initial_turn = false;
 
         // Repeat the round
-        } else if programmed_commands.len() <= 0 {
+        } else if self.programmed_commands.is_empty() {
             let ship = &game.ships[&me.ship_ids[0]];
 
-            programmed_commands.push(ship.move_ship(Direction::East));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::North));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::West));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::West));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::South));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::East));
+            self.programmed_commands.push(ship.move_ship(Direction::East));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::North));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::West));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::West));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::South));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::East));
             // Now ship is at spawn.
-            programmed_commands.push(ship.move_ship(Direction::East));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::South));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::West));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::West));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::North));
-            programmed_commands.push(ship.stay_still());
-            programmed_commands.push(ship.move_ship(Direction::East));
+            self.programmed_commands.push(ship.move_ship(Direction::East));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::South));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::West));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::West));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::North));
+            self.programmed_commands.push(ship.stay_still());
+            self.programmed_commands.push(ship.move_ship(Direction::East));
         }
 
         // Do one action per round.
-        match programmed_commands.pop() {
+        match self.programmed_commands.pop() {
             Some(com) => command_queue.push(com),
             None => game.log.borrow_mut().log("No programmed commands :(")
         }
 
-        Game::end_turn(&command_queue);
+        command_queue
     }
-}
\ No newline at end of file
+}
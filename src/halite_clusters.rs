@@ -0,0 +1,196 @@
+use hlt::direction::Direction;
+use hlt::game::Game;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::player::Player;
+use hlt::PlayerId;
+use hlt::position::Position;
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+/// Connected cells that all have at least the threshold of halite.
+#[derive(Clone, Debug)]
+pub struct Cluster {
+    pub cells: Vec<CellIndex>,
+    pub total_halite: usize,
+    /// Weighted by the halite of the cells. In an odd shaped
+    /// cluster, this doesn't have to be one of its cells.
+    pub centroid: Position,
+    /// From the centroid to the shipyard, indexed by player id.
+    pub shipyard_distances: Vec<usize>,
+}
+
+impl Cluster {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Halite per move it takes to get there from the shipyard.
+    pub fn score(&self, player_id: PlayerId) -> f64 {
+        self.total_halite as f64 / (self.shipyard_distances[player_id.0] + 1) as f64
+    }
+}
+
+/// The map split into clusters of rich cells. Clusters continue
+/// over the edges of the map, like the ships do.
+pub struct ClusterMap {
+    clusters: Vec<Cluster>,
+    /// Indexed by `CellIndex`, the position in `clusters`.
+    cluster_of: Vec<Option<usize>>,
+}
+
+impl ClusterMap {
+    pub fn new(game_map: &GameMap, players: &[Player], min_halite: usize) -> ClusterMap {
+        let size = game_map.width * game_map.height;
+        let mut cluster_of: Vec<Option<usize>> = vec![None; size];
+        let mut clusters = Vec::new();
+        // Where a cell is relative to the first cell of its cluster, without wrapping.
+        let mut offsets = vec![Position { x: 0, y: 0 }; size];
+        let mut queue = VecDeque::new();
+
+        for first in game_map.indices() {
+            if cluster_of[first.0].is_some() || game_map.cell(first).halite < min_halite {
+                continue;
+            }
+            let cluster_index = clusters.len();
+            cluster_of[first.0] = Some(cluster_index);
+            queue.push_back(first);
+            let mut cells = Vec::new();
+
+            while let Some(index) = queue.pop_front() {
+                cells.push(index);
+                let position = offsets[index.0];
+                for &direction in &[Direction::North, Direction::East, Direction::South, Direction::West] {
+                    let neighbour = game_map.neighbour(index, direction);
+                    if cluster_of[neighbour.0].is_none() && game_map.cell(neighbour).halite >= min_halite {
+                        cluster_of[neighbour.0] = Some(cluster_index);
+                        offsets[neighbour.0] = position + direction;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+
+            clusters.push(ClusterMap::describe(game_map, players, first, cells, &offsets));
+        }
+
+        ClusterMap { clusters, cluster_of }
+    }
+
+    pub fn for_game<R: BufRead>(game: &Game<R>, min_halite: usize) -> ClusterMap {
+        ClusterMap::new(&game.game_map, &game.players, min_halite)
+    }
+
+    pub fn clusters(&self) -> &[Cluster] {
+        &self.clusters
+    }
+
+    /// None if the cell has less halite than the threshold.
+    pub fn cluster_at(&self, index: CellIndex) -> Option<&Cluster> {
+        self.cluster_of[index.0].map(|cluster| &self.clusters[cluster])
+    }
+
+    /// The best cluster for the player first, by `Cluster::score`.
+    pub fn ranked(&self, player_id: PlayerId) -> Vec<&Cluster> {
+        let mut ranked: Vec<&Cluster> = self.clusters.iter().collect();
+        ranked.sort_by(|a, b| b.score(player_id).total_cmp(&a.score(player_id)));
+        ranked
+    }
+
+    fn describe(
+        game_map: &GameMap, players: &[Player], first: CellIndex, cells: Vec<CellIndex>, offsets: &[Position]
+    ) -> Cluster {
+        let total_halite: usize = cells.iter().map(|&index| game_map.cell(index).halite).sum();
+        let (mut sum_x, mut sum_y) = (0.0, 0.0);
+        for &index in &cells {
+            let weight = game_map.cell(index).halite as f64;
+            sum_x += offsets[index.0].x as f64 * weight;
+            sum_y += offsets[index.0].y as f64 * weight;
+        }
+        // Every cell is above the threshold, but that may be 0.
+        let weight = total_halite.max(1) as f64;
+        let offset = Position { x: (sum_x / weight).round() as i32, y: (sum_y / weight).round() as i32 };
+        let centroid = game_map.normalize(&(game_map.position_of(first) + offset));
+
+        let shipyard_distances = players.iter()
+            .map(|player| game_map.calculate_distance(&centroid, &player.shipyard.position))
+            .collect();
+        Cluster { cells, total_halite, centroid, shipyard_distances }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::shipyard::Shipyard;
+
+    fn player(id: usize, x: i32, y: i32) -> Player {
+        Player {
+            id: PlayerId(id),
+            shipyard: Shipyard { owner: PlayerId(id), position: Position { x, y } },
+            halite: 5000,
+            ship_ids: Vec::new(),
+            dropoff_ids: Vec::new(),
+        }
+    }
+
+    /// The halite of the map is given row by row.
+    fn map(width: usize, rows: &[&[usize]]) -> GameMap {
+        let halite: Vec<usize> = rows.iter().flat_map(|row| row.iter().cloned()).collect();
+        GameMap::new(width, rows.len(), &halite)
+    }
+
+    #[test]
+    fn connected_rich_cells_form_a_cluster() {
+        let game_map = map(8, &[
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+            &[0, 200, 200, 0, 0, 0, 0, 0],
+            &[0, 0, 400, 0, 0, 0, 300, 0],
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0, 0, 0],
+        ]);
+        let players = [player(0, 0, 0), player(1, 6, 3)];
+        let clusters = ClusterMap::new(&game_map, &players, 100);
+        assert_eq!(clusters.clusters().len(), 2);
+
+        let first = clusters.cluster_at(game_map.index_of(&Position { x: 1, y: 1 })).unwrap();
+        assert_eq!(first.area(), 3);
+        assert_eq!(first.total_halite, 800);
+        // Weighted by halite: x = (200 + 400 + 800) / 800, y = (200 + 200 + 800) / 800.
+        assert_eq!(first.centroid, Position { x: 2, y: 2 });
+        assert_eq!(first.shipyard_distances, vec![4, 5]);
+
+        let second = clusters.cluster_at(game_map.index_of(&Position { x: 6, y: 2 })).unwrap();
+        assert_eq!((second.area(), second.total_halite, second.centroid), (1, 300, Position { x: 6, y: 2 }));
+        assert!(clusters.cluster_at(game_map.index_of(&Position { x: 3, y: 1 })).is_none());
+
+        // Player 0 is closer to the big one, player 1 right next to the small one.
+        assert_eq!(clusters.ranked(PlayerId(0))[0].total_halite, 800);
+        assert_eq!(clusters.ranked(PlayerId(1))[0].total_halite, 300);
+    }
+
+    #[test]
+    fn clusters_continue_over_the_edges() {
+        let game_map = map(6, &[
+            &[500, 0, 0, 0, 0, 100],
+            &[0, 0, 0, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0],
+            &[500, 0, 0, 0, 0, 0],
+        ]);
+        let clusters = ClusterMap::new(&game_map, &[player(0, 3, 2)], 100);
+        assert_eq!(clusters.clusters().len(), 1);
+        let cluster = &clusters.clusters()[0];
+        assert_eq!(cluster.area(), 3);
+        assert_eq!(cluster.total_halite, 1100);
+        // About (-0.1, -0.45) from the first cell, so on the edge and not in the middle of the map.
+        assert_eq!(cluster.centroid, Position { x: 0, y: 0 });
+    }
+
+    #[test]
+    fn a_threshold_of_zero_joins_everything() {
+        let game_map = GameMap::new(5, 4, &[0; 20]);
+        let clusters = ClusterMap::new(&game_map, &[player(0, 0, 0)], 0);
+        assert_eq!(clusters.clusters().len(), 1);
+        assert_eq!(clusters.clusters()[0].area(), 20);
+    }
+}
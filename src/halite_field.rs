@@ -0,0 +1,268 @@
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::position::Position;
+use std::cmp::min;
+
+/// A value for every cell that aggregates the halite around it,
+/// e.g. the total halite within a radius. The sums wrap around
+/// the edges like the map does. A radius is limited to less than
+/// half the map, so every cell is counted once at most.
+#[derive(Clone)]
+pub struct HaliteField {
+    pub width: usize,
+    pub height: usize,
+    values: Vec<f64>,
+}
+
+impl HaliteField {
+    /// The halite of every cell itself.
+    pub fn halite(game_map: &GameMap) -> HaliteField {
+        HaliteField {
+            width: game_map.width,
+            height: game_map.height,
+            values: HaliteField::map_halite(game_map),
+        }
+    }
+
+    /// The halite in the square of `(2 * radius + 1)²` cells around every cell.
+    pub fn box_sum(game_map: &GameMap, radius: usize) -> HaliteField {
+        let (width, height) = (game_map.width, game_map.height);
+        let values = box_sums(&HaliteField::map_halite(game_map), width, height, radius, radius);
+        HaliteField { width, height, values }
+    }
+
+    /// The halite of every cell within `radius` moves.
+    pub fn diamond_sum(game_map: &GameMap, radius: usize) -> HaliteField {
+        let (width, height) = (game_map.width, game_map.height);
+        let radius = min(radius, (min(width, height) - 1) / 2);
+
+        // Tilted by 45°, the diamond becomes a square, so the halite of
+        // a padded copy of the map is put onto a grid where u = x + y and
+        // v = x - y. Only every second cell of that grid has a value.
+        let padded_width = width + 2 * radius;
+        let padded_height = height + 2 * radius;
+        let size = padded_width + padded_height - 1;
+        let mut prefix = vec![0.0; (size + 1) * (size + 1)];
+        for y in 0..padded_height {
+            for x in 0..padded_width {
+                let halite = game_map.at_position(&Position {
+                    x: x as i32 - radius as i32,
+                    y: y as i32 - radius as i32,
+                }).halite;
+                let u = x + y;
+                let v = x + padded_height - 1 - y;
+                prefix[(u + 1) * (size + 1) + v + 1] = halite as f64;
+            }
+        }
+        for u in 1..=size {
+            for v in 1..=size {
+                prefix[u * (size + 1) + v] += prefix[(u - 1) * (size + 1) + v]
+                    + prefix[u * (size + 1) + v - 1]
+                    - prefix[(u - 1) * (size + 1) + v - 1];
+            }
+        }
+
+        let rectangle = |u_from: usize, u_to: usize, v_from: usize, v_to: usize| {
+            prefix[u_to * (size + 1) + v_to] - prefix[u_from * (size + 1) + v_to]
+                - prefix[u_to * (size + 1) + v_from] + prefix[u_from * (size + 1) + v_from]
+        };
+        let mut values = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                // The center in the padded copy is (x + radius, y + radius).
+                let u = x + y + 2 * radius;
+                let v = x + padded_height - 1 - y;
+                values.push(rectangle(u - radius, u + radius + 1, v - radius, v + radius + 1));
+            }
+        }
+        HaliteField { width, height, values }
+    }
+
+    /// The halite around every cell, weighted with a Gaussian bell with a standard
+    /// deviation of `sigma` cells. The weights add up to 1, so it is a smoothed halite
+    /// per cell. Approximated with three box blurs of odd widths, which gets the spread
+    /// within about ten percent of sigma.
+    pub fn gaussian(game_map: &GameMap, sigma: f64) -> HaliteField {
+        let (width, height) = (game_map.width, game_map.height);
+        let mut values = HaliteField::map_halite(game_map);
+        for radius in gaussian_box_radii(sigma) {
+            let radius_x = min(radius, (width - 1) / 2);
+            let radius_y = min(radius, (height - 1) / 2);
+            let area = ((2 * radius_x + 1) * (2 * radius_y + 1)) as f64;
+            values = box_sums(&values, width, height, radius_x, radius_y).iter()
+                .map(|sum| sum / area)
+                .collect();
+        }
+        HaliteField { width, height, values }
+    }
+
+    /// Works with positions outside of the map, too.
+    pub fn at_position(&self, position: &Position) -> f64 {
+        let wrapped = position.wrap(self.width, self.height);
+        self.values[wrapped.y as usize * self.width + wrapped.x as usize]
+    }
+
+    pub fn at_index(&self, index: CellIndex) -> f64 {
+        self.values[index.0]
+    }
+
+    fn map_halite(game_map: &GameMap) -> Vec<f64> {
+        game_map.indices().map(|index| game_map.cell(index).halite as f64).collect()
+    }
+}
+
+/// Sums of the `(2 * radius_x + 1) * (2 * radius_y + 1)` rectangle around every cell,
+/// one prefix sum per row and then one per column.
+fn box_sums(values: &[f64], width: usize, height: usize, radius_x: usize, radius_y: usize) -> Vec<f64> {
+    let radius_x = min(radius_x, (width - 1) / 2);
+    let radius_y = min(radius_y, (height - 1) / 2);
+
+    let mut rows = vec![0.0; width * height];
+    let mut prefix = Vec::with_capacity(width + 2 * radius_x + 1);
+    for y in 0..height {
+        prefix.clear();
+        prefix.push(0.0);
+        for x in 0..width + 2 * radius_x {
+            let wrapped_x = (x + width - radius_x) % width;
+            let sum = prefix[x] + values[y * width + wrapped_x];
+            prefix.push(sum);
+        }
+        for x in 0..width {
+            rows[y * width + x] = prefix[x + 2 * radius_x + 1] - prefix[x];
+        }
+    }
+
+    let mut sums = vec![0.0; width * height];
+    let mut prefix = Vec::with_capacity(height + 2 * radius_y + 1);
+    for x in 0..width {
+        prefix.clear();
+        prefix.push(0.0);
+        for y in 0..height + 2 * radius_y {
+            let wrapped_y = (y + height - radius_y) % height;
+            let sum = prefix[y] + rows[wrapped_y * width + x];
+            prefix.push(sum);
+        }
+        for y in 0..height {
+            sums[y * width + x] = prefix[y + 2 * radius_y + 1] - prefix[y];
+        }
+    }
+    sums
+}
+
+/// The radii of three box blurs that together approximate a Gaussian.
+/// See "Fast Almost-Gaussian Filtering" by Peter Kovesi.
+fn gaussian_box_radii(sigma: f64) -> Vec<usize> {
+    const PASSES: f64 = 3.0;
+    let ideal_width = (12.0 * sigma * sigma / PASSES + 1.0).sqrt();
+    let mut lower_width = ideal_width.floor() as i64;
+    if lower_width % 2 == 0 {
+        lower_width -= 1;
+    }
+    let lower_width = lower_width.max(1);
+    let upper_width = lower_width + 2;
+    let lower_passes = ((12.0 * sigma * sigma
+        - PASSES * (lower_width * lower_width) as f64
+        - 4.0 * PASSES * lower_width as f64
+        - 3.0 * PASSES)
+        / (-4.0 * lower_width as f64 - 4.0)).round() as i64;
+
+    (0..PASSES as i64)
+        .map(|pass| if pass < lower_passes { lower_width } else { upper_width })
+        .map(|width| (width as usize - 1) / 2)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use strategy::seeded_rng;
+
+    fn random_map(seed: u64, width: usize, height: usize) -> GameMap {
+        let mut rng = seeded_rng(seed);
+        let halite: Vec<usize> = (0..width * height).map(|_| rng.gen_range(0, 1000)).collect();
+        GameMap::new(width, height, &halite)
+    }
+
+    /// The sum over all cells that pass the filter for the offset from the center.
+    fn brute_force<F>(game_map: &GameMap, center: CellIndex, includes: F) -> f64
+        where F: Fn(Position) -> bool
+    {
+        let center = game_map.position_of(center);
+        game_map.indices()
+            .filter(|&index| includes(game_map.delta(&center, &game_map.position_of(index))))
+            .map(|index| game_map.cell(index).halite as f64)
+            .sum()
+    }
+
+    #[test]
+    fn box_sum_matches_brute_force() {
+        for &(width, height, radius) in &[(8, 8, 0), (8, 8, 2), (9, 6, 3), (7, 5, 4), (32, 32, 5)] {
+            let game_map = random_map(1, width, height);
+            let field = HaliteField::box_sum(&game_map, radius);
+            let radius_x = min(radius, (width - 1) / 2) as i32;
+            let radius_y = min(radius, (height - 1) / 2) as i32;
+            for index in game_map.indices() {
+                let expected = brute_force(&game_map, index, |delta| {
+                    delta.x.abs() <= radius_x && delta.y.abs() <= radius_y
+                });
+                assert_eq!(field.at_index(index), expected, "{}x{} radius {}", width, height, radius);
+            }
+        }
+    }
+
+    #[test]
+    fn diamond_sum_matches_brute_force() {
+        for &(width, height, radius) in &[(8, 8, 0), (8, 8, 1), (9, 6, 2), (10, 7, 3), (6, 6, 5), (32, 32, 7)] {
+            let game_map = random_map(2, width, height);
+            let field = HaliteField::diamond_sum(&game_map, radius);
+            let radius = min(radius, (min(width, height) - 1) / 2);
+            for index in game_map.indices() {
+                let expected = brute_force(&game_map, index, |delta| delta.manhattan_length() <= radius);
+                assert_eq!(field.at_index(index), expected, "{}x{} radius {}", width, height, radius);
+            }
+        }
+    }
+
+    #[test]
+    fn at_position_wraps() {
+        let game_map = random_map(3, 6, 4);
+        let field = HaliteField::halite(&game_map);
+        assert_eq!(field.at_position(&Position { x: -1, y: 5 }), game_map.at_position(&Position { x: 5, y: 1 }).halite as f64);
+    }
+
+    #[test]
+    fn gaussian_keeps_the_total_halite() {
+        let game_map = random_map(4, 32, 24);
+        let total: f64 = game_map.indices().map(|index| game_map.cell(index).halite as f64).sum();
+        for &sigma in &[0.5, 1.0, 2.5, 6.0] {
+            let field = HaliteField::gaussian(&game_map, sigma);
+            let smoothed: f64 = game_map.indices().map(|index| field.at_index(index)).sum();
+            assert!((smoothed - total).abs() < 1e-6 * total, "sigma {}", sigma);
+        }
+
+        let flat = GameMap::new(10, 10, &[250; 100]);
+        let field = HaliteField::gaussian(&flat, 3.0);
+        assert!(flat.indices().all(|index| (field.at_index(index) - 250.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn gaussian_spreads_like_a_bell() {
+        let mut halite = vec![0; 31 * 31];
+        halite[15 * 31 + 15] = 1000;
+        let game_map = GameMap::new(31, 31, &halite);
+        let field = HaliteField::gaussian(&game_map, 2.0);
+        let at = |x: i32, y: i32| field.at_position(&Position { x, y });
+
+        assert!(at(15, 15) > at(16, 15) && at(16, 15) > at(17, 15) && at(17, 15) > at(19, 15));
+        assert!((at(16, 15) - at(14, 15)).abs() < 1e-9 && (at(15, 16) - at(15, 14)).abs() < 1e-9);
+        // The spread along one axis is close to sigma, the box widths are odd numbers.
+        let variance: f64 = (0..31)
+            .map(|x| {
+                let column: f64 = (0..31).map(|y| at(x, y)).sum();
+                column * ((x - 15) * (x - 15)) as f64
+            })
+            .sum::<f64>() / 1000.0;
+        assert!((variance.sqrt() - 2.0).abs() < 0.2, "variance {}", variance);
+    }
+}
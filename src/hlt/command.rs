@@ -1,18 +1,128 @@
 use hlt::direction::Direction;
+use hlt::error::HltError;
 use hlt::ShipId;
+use std::fmt;
+use std::str::FromStr;
 
-pub struct Command(pub String);
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    Spawn,
+    Move(ShipId, Direction),
+    Construct(ShipId),
+}
 
 impl Command {
     pub fn spawn_ship() -> Command {
-        Command(format!("g"))
+        Command::Spawn
     }
 
     pub fn transform_ship_into_dropoff_site(ship_id: ShipId) -> Command {
-        Command(format!("c {}", ship_id.0))
+        Command::Construct(ship_id)
     }
 
     pub fn move_ship(ship_id: ShipId, direction: Direction) -> Command {
-        Command(format!("m {} {}", ship_id.0, direction.get_char_encoding()))
+        Command::Move(ship_id, direction)
+    }
+
+    /// The ship this command is for. None for spawns.
+    pub fn ship_id(&self) -> Option<ShipId> {
+        match *self {
+            Command::Spawn => None,
+            Command::Move(ship_id, _) | Command::Construct(ship_id) => Some(ship_id),
+        }
+    }
+
+    /// Parses all commands of one turn, as a bot sends them in a single line.
+    pub fn parse_line(line: &str) -> Result<Vec<Command>, HltError> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let mut commands = Vec::new();
+        let mut start = 0;
+
+        while start < tokens.len() {
+            let length = match tokens[start] {
+                "g" => 1,
+                "c" => 2,
+                "m" => 3,
+                other => return Err(HltError::InvalidCommand(other.to_string())),
+            };
+            if start + length > tokens.len() {
+                return Err(HltError::InvalidCommand(tokens[start..].join(" ")));
+            }
+            commands.push(tokens[start..start + length].join(" ").parse()?);
+            start += length;
+        }
+        Ok(commands)
+    }
+}
+
+/// The wire format the engine expects.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Spawn => write!(f, "g"),
+            Command::Construct(ship_id) => write!(f, "c {}", ship_id.0),
+            Command::Move(ship_id, direction) => write!(f, "m {} {}", ship_id.0, direction.get_char_encoding()),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = HltError;
+
+    fn from_str(s: &str) -> Result<Command, HltError> {
+        let invalid = || HltError::InvalidCommand(s.to_string());
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        let ship_id = |token: &str| token.parse().map(ShipId).map_err(|_| invalid());
+
+        match tokens.as_slice() {
+            ["g"] => Ok(Command::Spawn),
+            ["c", id] => Ok(Command::Construct(ship_id(id)?)),
+            ["m", id, direction] => {
+                let mut chars = direction.chars();
+                let direction = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Direction::from_char(c).ok_or_else(invalid)?,
+                    _ => return Err(invalid()),
+                };
+                Ok(Command::Move(ship_id(id)?, direction))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_round_trip_through_the_wire_format() {
+        let commands = vec![
+            Command::Spawn,
+            Command::Construct(ShipId(12)),
+            Command::Move(ShipId(0), Direction::North),
+            Command::Move(ShipId(3), Direction::South),
+            Command::Move(ShipId(4), Direction::East),
+            Command::Move(ShipId(5), Direction::West),
+            Command::Move(ShipId(6), Direction::Still),
+        ];
+        let line: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+        assert_eq!(line.join(" "), "g c 12 m 0 n m 3 s m 4 e m 5 w m 6 o");
+        for command in &commands {
+            assert_eq!(command.to_string().parse::<Command>().unwrap(), *command);
+        }
+        assert_eq!(Command::parse_line(&line.join(" ")).unwrap(), commands);
+        // As Game::end_turn writes it, with a space after every command.
+        assert_eq!(Command::parse_line("m 1 n g \n").unwrap(), vec![Command::Move(ShipId(1), Direction::North), Command::Spawn]);
+        assert_eq!(Command::parse_line("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn malformed_commands_are_rejected() {
+        for text in &["", "x", "g 1", "c", "c one", "m 1", "m 1 x", "m 1 nn", "m -1 n", "c 1 2"] {
+            assert!(matches!(text.parse::<Command>(), Err(HltError::InvalidCommand(_))), "{}", text);
+        }
+        assert!(matches!(Command::parse_line("g m 1"), Err(HltError::InvalidCommand(ref rest)) if rest == "m 1"));
+        assert!(matches!(Command::parse_line("g q"), Err(HltError::InvalidCommand(ref token)) if token == "q"));
+        assert!(matches!(Command::parse_line("m 1 n 2"), Err(HltError::InvalidCommand(_))));
     }
 }
@@ -0,0 +1,284 @@
+use hlt::command::Command;
+use hlt::constants::Constants;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::player::Player;
+use hlt::ship::Ship;
+use hlt::ShipId;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::BufRead;
+
+/// Reasons why the engine would reject a turn and kill the bot.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CommandError {
+    /// The ship doesn't exist or belongs to another player.
+    NotOwnShip(ShipId),
+    /// The ship got more than one command.
+    DuplicateCommand(ShipId),
+    /// Only one ship can be spawned per turn.
+    DuplicateSpawn,
+    /// A dropoff can't be built on a shipyard or another dropoff.
+    ConstructOnStructure(ShipId),
+    /// Spawns and dropoffs cost more than the player has,
+    /// including the credits from converted ships.
+    NotEnoughHalite { needed: usize, available: usize },
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::NotOwnShip(ship_id) => write!(f, "Player doesn't own ship {}.", ship_id.0),
+            CommandError::DuplicateCommand(ship_id) => write!(f, "Ship {} got more than one command.", ship_id.0),
+            CommandError::DuplicateSpawn => write!(f, "Spawned more than once in a turn."),
+            CommandError::ConstructOnStructure(ship_id) =>
+                write!(f, "Ship {} can't build a dropoff on another structure.", ship_id.0),
+            CommandError::NotEnoughHalite { needed, available } =>
+                write!(f, "Needs {} halite, but only has {}.", needed, available),
+        }
+    }
+}
+
+/// The commands of one turn. Can be checked against the
+/// game state before it is sent with `Game::end_turn`.
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Vec<Command>,
+}
+
+impl CommandQueue {
+    pub fn new() -> CommandQueue {
+        CommandQueue { commands: Vec::new() }
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn has_command_for(&self, ship_id: ShipId) -> bool {
+        self.commands.iter().any(|command| command.ship_id() == Some(ship_id))
+    }
+
+    /// Checks the commands against the state of my player.
+    pub fn validate<R: BufRead>(&self, game: &Game<R>) -> Result<(), Vec<CommandError>> {
+        let errors = CommandQueue::check(
+            &self.commands, &game.players[game.my_id.0], &game.ships, &game.game_map, &game.constants);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Finds everything the engine would reject in the commands of that player.
+    pub fn check(
+        commands: &[Command],
+        player: &Player,
+        ships: &HashMap<ShipId, Ship>,
+        game_map: &GameMap,
+        constants: &Constants,
+    ) -> Vec<CommandError> {
+        let mut errors = Vec::new();
+        let mut commanded_ships: HashSet<ShipId> = HashSet::new();
+        let mut spawned = false;
+        let mut available = player.halite;
+        let mut needed = 0;
+
+        for command in commands {
+            let ship_id = match *command {
+                Command::Spawn => {
+                    if spawned {
+                        errors.push(CommandError::DuplicateSpawn);
+                    }
+                    spawned = true;
+                    needed += constants.ship_cost;
+                    continue;
+                },
+                Command::Move(ship_id, _) | Command::Construct(ship_id) => ship_id,
+            };
+            let ship = match ships.get(&ship_id) {
+                Some(ship) if ship.owner == player.id => ship,
+                _ => {
+                    errors.push(CommandError::NotOwnShip(ship_id));
+                    continue;
+                }
+            };
+            if !commanded_ships.insert(ship_id) {
+                errors.push(CommandError::DuplicateCommand(ship_id));
+            }
+
+            if let Command::Construct(_) = command {
+                let cell = game_map.at_entity(ship);
+                if cell.has_structure() {
+                    errors.push(CommandError::ConstructOnStructure(ship_id));
+                }
+                // The cargo and the halite in the cell are credited first.
+                available += ship.halite + cell.halite;
+                needed += constants.dropoff_cost;
+            }
+        }
+
+        if needed > available {
+            errors.push(CommandError::NotEnoughHalite { needed, available });
+        }
+        errors
+    }
+
+    /// Drops the commands the engine would reject, so it doesn't kill the bot for them.
+    /// A command is kept if it is valid together with the commands kept before it.
+    /// Returns the kept commands and why the others were dropped.
+    pub fn without_invalid(
+        commands: &[Command],
+        player: &Player,
+        ships: &HashMap<ShipId, Ship>,
+        game_map: &GameMap,
+        constants: &Constants,
+    ) -> (Vec<Command>, Vec<CommandError>) {
+        let mut kept: Vec<Command> = Vec::with_capacity(commands.len());
+        let mut dropped = Vec::new();
+        for command in commands {
+            kept.push(*command);
+            let errors = CommandQueue::check(&kept, player, ships, game_map, constants);
+            if let Some(error) = errors.into_iter().next() {
+                kept.pop();
+                dropped.push(error);
+            }
+        }
+        (kept, dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::direction::Direction;
+    use hlt::DropoffId;
+    use hlt::map_cell::Structure;
+    use hlt::PlayerId;
+    use hlt::position::Position;
+    use hlt::shipyard::Shipyard;
+
+    const MINE: ShipId = ShipId(0);
+    const LOADED: ShipId = ShipId(1);
+    const THEIRS: ShipId = ShipId(2);
+
+    struct State {
+        player: Player,
+        ships: HashMap<ShipId, Ship>,
+        game_map: GameMap,
+        constants: Constants,
+    }
+
+    impl State {
+        /// Player 0 has 1500 halite, an empty ship at (1, 1) and one with 800 halite
+        /// at (2, 2) on a cell with 500. Player 1 has a ship at (3, 3) and a dropoff at (0, 3).
+        fn new() -> State {
+            let mut game_map = GameMap::new(4, 4, &[0; 16]);
+            game_map.at_position_mut(&Position { x: 2, y: 2 }).halite = 500;
+            game_map.at_position_mut(&Position { x: 0, y: 0 }).structure = Structure::Shipyard(PlayerId(0));
+            game_map.at_position_mut(&Position { x: 0, y: 3 }).structure = Structure::Dropoff(DropoffId(0));
+            let ships = vec![
+                Ship::new(PlayerId(0), MINE, Position { x: 1, y: 1 }, 0, 1000),
+                Ship::new(PlayerId(0), LOADED, Position { x: 2, y: 2 }, 800, 1000),
+                Ship::new(PlayerId(1), THEIRS, Position { x: 3, y: 3 }, 0, 1000),
+            ];
+            State {
+                player: Player {
+                    id: PlayerId(0),
+                    shipyard: Shipyard { owner: PlayerId(0), position: Position { x: 0, y: 0 } },
+                    halite: 1500,
+                    ship_ids: vec![MINE, LOADED],
+                    dropoff_ids: Vec::new(),
+                },
+                ships: ships.into_iter().map(|ship| (ship.id, ship)).collect(),
+                game_map,
+                constants: Constants::default(),
+            }
+        }
+
+        fn check(&self, commands: &[Command]) -> Vec<CommandError> {
+            CommandQueue::check(commands, &self.player, &self.ships, &self.game_map, &self.constants)
+        }
+
+        fn without_invalid(&self, commands: &[Command]) -> (Vec<Command>, Vec<CommandError>) {
+            CommandQueue::without_invalid(commands, &self.player, &self.ships, &self.game_map, &self.constants)
+        }
+    }
+
+    #[test]
+    fn a_valid_turn_has_no_errors() {
+        let state = State::new();
+        assert_eq!(state.check(&[]), vec![]);
+        assert_eq!(state.check(&[
+            Command::Spawn,
+            Command::Move(MINE, Direction::North),
+            Command::Move(LOADED, Direction::Still),
+        ]), vec![]);
+    }
+
+    #[test]
+    fn one_command_per_ship() {
+        let state = State::new();
+        assert_eq!(state.check(&[Command::Move(MINE, Direction::North), Command::Move(MINE, Direction::North)]),
+            vec![CommandError::DuplicateCommand(MINE)]);
+    }
+
+    #[test]
+    fn one_spawn_per_turn() {
+        let mut state = State::new();
+        state.player.halite = 5000;
+        assert_eq!(state.check(&[Command::Spawn, Command::Spawn]), vec![CommandError::DuplicateSpawn]);
+    }
+
+    #[test]
+    fn only_own_ships_get_commands() {
+        let state = State::new();
+        assert_eq!(state.check(&[Command::Move(THEIRS, Direction::West)]), vec![CommandError::NotOwnShip(THEIRS)]);
+        assert_eq!(state.check(&[Command::Construct(ShipId(9))]), vec![CommandError::NotOwnShip(ShipId(9))]);
+    }
+
+    #[test]
+    fn no_dropoff_on_a_structure() {
+        let mut state = State::new();
+        state.player.halite = 5000;
+        for &position in &[Position { x: 0, y: 0 }, Position { x: 0, y: 3 }] {
+            state.ships.get_mut(&MINE).unwrap().position = position;
+            assert_eq!(state.check(&[Command::Construct(MINE)]), vec![CommandError::ConstructOnStructure(MINE)]);
+        }
+    }
+
+    #[test]
+    fn spawns_and_dropoffs_need_enough_halite() {
+        let mut state = State::new();
+        state.player.halite = 999;
+        assert_eq!(state.check(&[Command::Spawn]),
+            vec![CommandError::NotEnoughHalite { needed: 1000, available: 999 }]);
+        // The cargo and the cell pay for most of the dropoff.
+        state.player.halite = 2700;
+        assert_eq!(state.check(&[Command::Construct(LOADED)]), vec![]);
+        assert_eq!(state.check(&[Command::Construct(LOADED), Command::Spawn]),
+            vec![CommandError::NotEnoughHalite { needed: 5000, available: 4000 }]);
+    }
+
+    #[test]
+    fn without_invalid_keeps_what_fits_together() {
+        let state = State::new();
+        let (kept, dropped) = state.without_invalid(&[
+            Command::Move(MINE, Direction::East),
+            Command::Move(THEIRS, Direction::West),
+            Command::Spawn,
+            Command::Move(MINE, Direction::South),
+            Command::Spawn,
+            Command::Construct(LOADED),
+        ]);
+        assert_eq!(kept, vec![Command::Move(MINE, Direction::East), Command::Spawn]);
+        assert_eq!(dropped, vec![
+            CommandError::NotOwnShip(THEIRS),
+            CommandError::DuplicateCommand(MINE),
+            CommandError::DuplicateSpawn,
+            CommandError::NotEnoughHalite { needed: 5000, available: 2800 },
+        ]);
+        assert_eq!(state.check(&kept), vec![]);
+    }
+}
@@ -1,7 +1,14 @@
-use hlt::log::Log;
+use hlt::error::HltError;
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Map;
+use serde_json::Value;
+use std::cmp::min;
 use std::collections::HashMap;
-use std::str::FromStr;
 
+/// All constants the engine sends in the first line, as a JSON object.
+/// Keys that aren't documented end up in `extra`.
+#[derive(Clone)]
 pub struct Constants {
     pub max_halite: usize,
     pub ship_cost: usize,
@@ -15,52 +22,281 @@ pub struct Constants {
     pub inspired_extract_ratio: usize,
     pub inspired_bonus_multiplier: f64,
     pub inspired_move_cost_ratio: usize,
+
+    // Older engines don't send these, so they fall back to the defaults.
+    pub capture_enabled: bool,
+    pub capture_radius: usize,
+    pub ships_above_for_capture: usize,
+    pub default_map_width: usize,
+    pub default_map_height: usize,
+    pub dropoff_penalty_ratio: usize,
+    pub initial_halite: usize,
+    pub max_players: usize,
+    pub min_turns: usize,
+    pub min_turn_threshold: usize,
+    pub max_turn_threshold: usize,
+    pub min_cell_production: usize,
+    pub max_cell_production: usize,
+    pub factor_exp_1: f64,
+    pub factor_exp_2: f64,
+    pub persistence: f64,
+    pub strict_errors: bool,
+    /// The seed the engine generated the map with.
+    pub game_seed: Option<u64>,
+
+    /// Every key without a field above.
+    pub extra: HashMap<String, Value>,
+}
+
+/// The values of the official engine for a 32x32 map.
+impl Default for Constants {
+    fn default() -> Constants {
+        Constants {
+            max_halite: 1000,
+            ship_cost: 1000,
+            dropoff_cost: 4000,
+            max_turns: 400,
+            extract_ratio: 4,
+            move_cost_ratio: 10,
+            inspiration_enabled: true,
+            inspiration_radius: 4,
+            inspiration_ship_count: 2,
+            inspired_extract_ratio: 4,
+            inspired_bonus_multiplier: 2.0,
+            inspired_move_cost_ratio: 10,
+            capture_enabled: false,
+            capture_radius: 3,
+            ships_above_for_capture: 3,
+            default_map_width: 32,
+            default_map_height: 32,
+            dropoff_penalty_ratio: 4,
+            initial_halite: 5000,
+            max_players: 16,
+            min_turns: 400,
+            min_turn_threshold: 32,
+            max_turn_threshold: 64,
+            min_cell_production: 900,
+            max_cell_production: 1000,
+            factor_exp_1: 2.0,
+            factor_exp_2: 2.0,
+            persistence: 0.7,
+            strict_errors: false,
+            game_seed: None,
+            extra: HashMap::new(),
+        }
+    }
 }
 
 impl Constants {
-    pub fn new(log: &mut Log, string_from_engine: &str) -> Constants {
-        let token_iter = string_from_engine.split(|c| " {},:\"\r\n".contains(c));
-        let token_iter = token_iter.filter(|x| !x.is_empty());
-        let tokens: Vec<&str> = token_iter.collect();
+    pub fn new(string_from_engine: &str) -> Result<Constants, HltError> {
+        let mut map = match serde_json::from_str(string_from_engine) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => return Err(HltError::InvalidConstants("expected a JSON object from server.".to_string())),
+            Err(error) => return Err(HltError::InvalidConstants(error.to_string())),
+        };
+        let defaults = Constants::default();
 
-        if (tokens.len() % 2) != 0 {
-            log.panic("Error: constants: expected even total number of key and value tokens from server.");
-        }
+        Ok(Constants {
+            ship_cost: Constants::take_value(&mut map, "NEW_ENTITY_ENERGY_COST")?,
+            dropoff_cost: Constants::take_value(&mut map, "DROPOFF_COST")?,
+            max_halite: Constants::take_value(&mut map, "MAX_ENERGY")?,
+            max_turns: Constants::take_value(&mut map, "MAX_TURNS")?,
+            extract_ratio: Constants::take_value(&mut map, "EXTRACT_RATIO")?,
+            move_cost_ratio: Constants::take_value(&mut map, "MOVE_COST_RATIO")?,
+            inspiration_enabled: Constants::take_value(&mut map, "INSPIRATION_ENABLED")?,
+            inspiration_radius: Constants::take_value(&mut map, "INSPIRATION_RADIUS")?,
+            inspiration_ship_count: Constants::take_value(&mut map, "INSPIRATION_SHIP_COUNT")?,
+            inspired_extract_ratio: Constants::take_value(&mut map, "INSPIRED_EXTRACT_RATIO")?,
+            inspired_bonus_multiplier: Constants::take_value(&mut map, "INSPIRED_BONUS_MULTIPLIER")?,
+            inspired_move_cost_ratio: Constants::take_value(&mut map, "INSPIRED_MOVE_COST_RATIO")?,
+            capture_enabled: Constants::take_or(&mut map, "CAPTURE_ENABLED", defaults.capture_enabled)?,
+            capture_radius: Constants::take_or(&mut map, "CAPTURE_RADIUS", defaults.capture_radius)?,
+            ships_above_for_capture: Constants::take_or(&mut map, "SHIPS_ABOVE_FOR_CAPTURE", defaults.ships_above_for_capture)?,
+            default_map_width: Constants::take_or(&mut map, "DEFAULT_MAP_WIDTH", defaults.default_map_width)?,
+            default_map_height: Constants::take_or(&mut map, "DEFAULT_MAP_HEIGHT", defaults.default_map_height)?,
+            dropoff_penalty_ratio: Constants::take_or(&mut map, "DROPOFF_PENALTY_RATIO", defaults.dropoff_penalty_ratio)?,
+            initial_halite: Constants::take_or(&mut map, "INITIAL_ENERGY", defaults.initial_halite)?,
+            max_players: Constants::take_or(&mut map, "MAX_PLAYERS", defaults.max_players)?,
+            min_turns: Constants::take_or(&mut map, "MIN_TURNS", defaults.min_turns)?,
+            min_turn_threshold: Constants::take_or(&mut map, "MIN_TURN_THRESHOLD", defaults.min_turn_threshold)?,
+            max_turn_threshold: Constants::take_or(&mut map, "MAX_TURN_THRESHOLD", defaults.max_turn_threshold)?,
+            min_cell_production: Constants::take_or(&mut map, "MIN_CELL_PRODUCTION", defaults.min_cell_production)?,
+            max_cell_production: Constants::take_or(&mut map, "MAX_CELL_PRODUCTION", defaults.max_cell_production)?,
+            factor_exp_1: Constants::take_or(&mut map, "FACTOR_EXP_1", defaults.factor_exp_1)?,
+            factor_exp_2: Constants::take_or(&mut map, "FACTOR_EXP_2", defaults.factor_exp_2)?,
+            persistence: Constants::take_or(&mut map, "PERSISTENCE", defaults.persistence)?,
+            strict_errors: Constants::take_or(&mut map, "STRICT_ERRORS", defaults.strict_errors)?,
+            game_seed: Constants::take_or(&mut map, "game_seed", defaults.game_seed)?,
+            extra: map.into_iter().collect(),
+        })
+    }
 
-        let mut map = HashMap::new();
+    /// The JSON object `new` reads, e.g. to write it into a replay.
+    pub fn to_json(&self) -> Value {
+        let mut map = match json!({
+            "NEW_ENTITY_ENERGY_COST": self.ship_cost,
+            "DROPOFF_COST": self.dropoff_cost,
+            "MAX_ENERGY": self.max_halite,
+            "MAX_TURNS": self.max_turns,
+            "EXTRACT_RATIO": self.extract_ratio,
+            "MOVE_COST_RATIO": self.move_cost_ratio,
+            "INSPIRATION_ENABLED": self.inspiration_enabled,
+            "INSPIRATION_RADIUS": self.inspiration_radius,
+            "INSPIRATION_SHIP_COUNT": self.inspiration_ship_count,
+            "INSPIRED_EXTRACT_RATIO": self.inspired_extract_ratio,
+            "INSPIRED_BONUS_MULTIPLIER": self.inspired_bonus_multiplier,
+            "INSPIRED_MOVE_COST_RATIO": self.inspired_move_cost_ratio,
+            "CAPTURE_ENABLED": self.capture_enabled,
+            "CAPTURE_RADIUS": self.capture_radius,
+            "SHIPS_ABOVE_FOR_CAPTURE": self.ships_above_for_capture,
+            "DEFAULT_MAP_WIDTH": self.default_map_width,
+            "DEFAULT_MAP_HEIGHT": self.default_map_height,
+            "DROPOFF_PENALTY_RATIO": self.dropoff_penalty_ratio,
+            "INITIAL_ENERGY": self.initial_halite,
+            "MAX_PLAYERS": self.max_players,
+            "MIN_TURNS": self.min_turns,
+            "MIN_TURN_THRESHOLD": self.min_turn_threshold,
+            "MAX_TURN_THRESHOLD": self.max_turn_threshold,
+            "MIN_CELL_PRODUCTION": self.min_cell_production,
+            "MAX_CELL_PRODUCTION": self.max_cell_production,
+            "FACTOR_EXP_1": self.factor_exp_1,
+            "FACTOR_EXP_2": self.factor_exp_2,
+            "PERSISTENCE": self.persistence,
+            "STRICT_ERRORS": self.strict_errors,
+        }) {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        };
 
-        for i in (0..tokens.len()).step_by(2) {
-            map.insert(tokens[i].to_string(), tokens[i+1].to_string());
+        if let Some(seed) = self.game_seed {
+            map.insert("game_seed".to_string(), json!(seed));
+        }
+        for (key, value) in &self.extra {
+            map.insert(key.clone(), value.clone());
         }
+        Value::Object(map)
+    }
 
-        Constants {
-            ship_cost: Constants::get_value(log, &map, "NEW_ENTITY_ENERGY_COST"),
-            dropoff_cost: Constants::get_value(log, &map, "DROPOFF_COST"),
-            max_halite: Constants::get_value(log, &map, "MAX_ENERGY"),
-            max_turns: Constants::get_value(log, &map, "MAX_TURNS"),
-            extract_ratio: Constants::get_value(log, &map, "EXTRACT_RATIO"),
-            move_cost_ratio: Constants::get_value(log, &map, "MOVE_COST_RATIO"),
-            inspiration_enabled: Constants::get_value(log, &map, "INSPIRATION_ENABLED"),
-            inspiration_radius: Constants::get_value(log, &map, "INSPIRATION_RADIUS"),
-            inspiration_ship_count: Constants::get_value(log, &map, "INSPIRATION_SHIP_COUNT"),
-            inspired_extract_ratio: Constants::get_value(log, &map, "INSPIRED_EXTRACT_RATIO"),
-            inspired_bonus_multiplier: Constants::get_value(log, &map, "INSPIRED_BONUS_MULTIPLIER"),
-            inspired_move_cost_ratio: Constants::get_value(log, &map, "INSPIRED_MOVE_COST_RATIO"),
+    /// The halite taken from a cell with `cell_halite` and what the ship gets
+    /// for it, bonus included. Both are limited by the `free_space` of the ship.
+    pub fn mining_yield(&self, cell_halite: usize, inspired: bool, free_space: usize) -> (usize, usize) {
+        let ratio = if inspired {
+            self.inspired_extract_ratio
+        } else {
+            self.extract_ratio
+        };
+        let extracted = min(cell_halite.div_ceil(ratio), free_space);
+        let mut gained = extracted;
+        if inspired {
+            gained += (extracted as f64 * self.inspired_bonus_multiplier) as usize;
         }
+        (extracted, min(gained, free_space))
     }
 
-    fn get_value<T: FromStr>(log: &mut Log, map: &HashMap<String, String>, key: &str) -> T {
-        let s = Constants::get_string(log, map, key);
-        match s.parse::<T>() {
-            Ok(x) => x,
-            Err(_) => log.panic(&format!("Error: constants: for {} got '{}' from server and failed to parse that.", key, s))
+    /// Removes the key from the map, so only unknown keys remain.
+    fn take_value<T: DeserializeOwned>(map: &mut Map<String, Value>, key: &str) -> Result<T, HltError> {
+        match map.remove(key) {
+            Some(value) => Constants::parse_value(key, value),
+            None => Err(HltError::MissingConstant(key.to_string())),
         }
     }
 
-    fn get_string<'a>(log: &mut Log, map: &'a HashMap<String, String>, key: &str) -> &'a String {
-        match map.get(key) {
-            Some(x) => x,
-            None => log.panic(&format!("Error: constants: server did not send {} constant.", key))
+    fn take_or<T: DeserializeOwned>(map: &mut Map<String, Value>, key: &str, default: T) -> Result<T, HltError> {
+        match map.remove(key) {
+            Some(value) => Constants::parse_value(key, value),
+            None => Ok(default),
         }
     }
+
+    fn parse_value<T: DeserializeOwned>(key: &str, value: Value) -> Result<T, HltError> {
+        serde_json::from_value(value.clone())
+            .map_err(|_| HltError::InvalidConstant { key: key.to_string(), value: value.to_string() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What the official engine sends, plus a key it might add in a later version.
+    const ENGINE_LINE: &str = "{\"CAPTURE_ENABLED\":false,\"CAPTURE_RADIUS\":3,\"DEFAULT_MAP_HEIGHT\":48,\
+        \"DEFAULT_MAP_WIDTH\":48,\"DROPOFF_COST\":4000,\"DROPOFF_PENALTY_RATIO\":4,\"EXTRACT_RATIO\":4,\
+        \"FACTOR_EXP_1\":2.0,\"FACTOR_EXP_2\":2.0,\"INITIAL_ENERGY\":5000,\"INSPIRATION_ENABLED\":true,\
+        \"INSPIRATION_RADIUS\":4,\"INSPIRATION_SHIP_COUNT\":2,\"INSPIRED_BONUS_MULTIPLIER\":2.0,\
+        \"INSPIRED_EXTRACT_RATIO\":4,\"INSPIRED_MOVE_COST_RATIO\":10,\"MAX_CELL_PRODUCTION\":1000,\
+        \"MAX_ENERGY\":1000,\"MAX_PLAYERS\":16,\"MAX_TURNS\":450,\"MAX_TURN_THRESHOLD\":64,\
+        \"MIN_CELL_PRODUCTION\":900,\"MIN_TURNS\":400,\"MIN_TURN_THRESHOLD\":32,\"MOVE_COST_RATIO\":10,\
+        \"NEW_ENTITY_ENERGY_COST\":1000,\"PERSISTENCE\":0.7,\"SHIPS_ABOVE_FOR_CAPTURE\":3,\
+        \"STRICT_ERRORS\":false,\"game_seed\":1542409934,\"NEW_RULE\":[1,2]}";
+
+    #[test]
+    fn the_engine_line_is_parsed() {
+        let constants = Constants::new(ENGINE_LINE).unwrap();
+        assert_eq!(constants.max_turns, 450);
+        assert_eq!(constants.ship_cost, 1000);
+        assert_eq!(constants.dropoff_cost, 4000);
+        assert_eq!((constants.default_map_width, constants.default_map_height), (48, 48));
+        assert_eq!(constants.inspired_bonus_multiplier, 2.0);
+        assert!(constants.inspiration_enabled);
+        assert!(!constants.strict_errors);
+        assert_eq!(constants.game_seed, Some(1542409934));
+        assert_eq!(constants.extra.len(), 1);
+        assert_eq!(constants.extra["NEW_RULE"], json!([1, 2]));
+    }
+
+    #[test]
+    fn optional_keys_fall_back_to_the_defaults() {
+        let constants = Constants::new("{\"NEW_ENTITY_ENERGY_COST\":500,\"DROPOFF_COST\":2000,\"MAX_ENERGY\":800,\
+            \"MAX_TURNS\":300,\"EXTRACT_RATIO\":3,\"MOVE_COST_RATIO\":5,\"INSPIRATION_ENABLED\":false,\
+            \"INSPIRATION_RADIUS\":2,\"INSPIRATION_SHIP_COUNT\":1,\"INSPIRED_EXTRACT_RATIO\":2,\
+            \"INSPIRED_BONUS_MULTIPLIER\":1.5,\"INSPIRED_MOVE_COST_RATIO\":6}").unwrap();
+        assert_eq!(constants.ship_cost, 500);
+        assert_eq!(constants.initial_halite, Constants::default().initial_halite);
+        assert_eq!(constants.game_seed, None);
+        assert!(constants.extra.is_empty());
+    }
+
+    #[test]
+    fn a_malformed_line_is_an_error() {
+        assert!(matches!(Constants::new("{\"MAX_TURNS\":"), Err(HltError::InvalidConstants(_))));
+        assert!(matches!(Constants::new("[1, 2]"), Err(HltError::InvalidConstants(_))));
+        assert!(matches!(Constants::new("{}"), Err(HltError::MissingConstant(_))));
+        let without_ship_cost = ENGINE_LINE.replace("\"NEW_ENTITY_ENERGY_COST\":1000,", "");
+        assert!(matches!(Constants::new(&without_ship_cost),
+            Err(HltError::MissingConstant(ref key)) if key == "NEW_ENTITY_ENERGY_COST"));
+        let negative = ENGINE_LINE.replace("\"MAX_TURNS\":450", "\"MAX_TURNS\":-1");
+        assert!(matches!(Constants::new(&negative),
+            Err(HltError::InvalidConstant { ref key, .. }) if key == "MAX_TURNS"));
+    }
+
+    #[test]
+    fn to_json_reads_back_the_same() {
+        let constants = Constants::new(ENGINE_LINE).unwrap();
+        let json = constants.to_json();
+        assert_eq!(json, serde_json::from_str::<Value>(ENGINE_LINE).unwrap());
+        let again = Constants::new(&json.to_string()).unwrap();
+        assert_eq!(again.to_json(), json);
+
+        let defaults = Constants::default();
+        assert_eq!(Constants::new(&defaults.to_json().to_string()).unwrap().to_json(), defaults.to_json());
+    }
+
+    #[test]
+    fn mining_rounds_up_and_inspiration_adds_the_bonus() {
+        let constants = Constants::default();
+        assert_eq!(constants.mining_yield(0, false, 1000), (0, 0));
+        assert_eq!(constants.mining_yield(1, false, 1000), (1, 1));
+        assert_eq!(constants.mining_yield(10, false, 1000), (3, 3));
+        assert_eq!(constants.mining_yield(12, false, 1000), (3, 3));
+        assert_eq!(constants.mining_yield(10, true, 1000), (3, 9));
+        assert_eq!(constants.mining_yield(1, true, 1000), (1, 3));
+    }
+
+    #[test]
+    fn mining_is_limited_by_the_free_space() {
+        let constants = Constants::default();
+        assert_eq!(constants.mining_yield(1000, false, 100), (100, 100));
+        assert_eq!(constants.mining_yield(1000, true, 400), (250, 400));
+        assert_eq!(constants.mining_yield(1000, true, 0), (0, 0));
+    }
 }
@@ -1,4 +1,4 @@
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Direction {
     North,
     East,
@@ -31,4 +31,42 @@ impl Direction {
             Direction::Still => 'o',
         }
     }
+
+    /// The opposite of `get_char_encoding`.
+    pub fn from_char(c: char) -> Option<Direction> {
+        match c {
+            'n' => Some(Direction::North),
+            'e' => Some(Direction::East),
+            's' => Some(Direction::South),
+            'w' => Some(Direction::West),
+            'o' => Some(Direction::Still),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_char_reads_back_every_encoding() {
+        for direction in Direction::get_all_cardinals().into_iter().chain(Some(Direction::Still)) {
+            assert_eq!(Direction::from_char(direction.get_char_encoding()), Some(direction));
+        }
+        assert_eq!(Direction::from_char('N'), None);
+        assert_eq!(Direction::from_char('x'), None);
+        assert_eq!(Direction::from_char(' '), None);
+    }
+
+    #[test]
+    fn inverting_twice_is_the_same_direction() {
+        assert_eq!(Direction::North.invert_direction(), Direction::South);
+        assert_eq!(Direction::East.invert_direction(), Direction::West);
+        assert_eq!(Direction::Still.invert_direction(), Direction::Still);
+        for direction in Direction::get_all_cardinals() {
+            assert_ne!(direction.invert_direction(), direction);
+            assert_eq!(direction.invert_direction().invert_direction(), direction);
+        }
+    }
 }
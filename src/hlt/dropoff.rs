@@ -1,9 +1,12 @@
 use hlt::DropoffId;
 use hlt::entity::Entity;
+use hlt::error::HltError;
 use hlt::input::Input;
 use hlt::PlayerId;
 use hlt::position::Position;
+use std::io::BufRead;
 
+#[derive(Clone)]
 pub struct Dropoff {
     pub owner: PlayerId,
     pub id: DropoffId,
@@ -21,12 +24,12 @@ impl Entity for Dropoff {
 }
 
 impl Dropoff {
-    pub fn generate(input: &mut Input, player_id: PlayerId) -> Dropoff {
-        input.read_and_parse_line();
-        let id = DropoffId(input.next_usize());
-        let x = input.next_i32();
-        let y = input.next_i32();
+    pub fn generate<R: BufRead>(input: &mut Input<R>, player_id: PlayerId) -> Result<Dropoff, HltError> {
+        input.read_and_parse_line()?;
+        let id = DropoffId(input.next_usize()?);
+        let x = input.next_i32()?;
+        let y = input.next_i32()?;
 
-        Dropoff { owner: player_id, id, position: Position { x, y } }
+        Ok(Dropoff { owner: player_id, id, position: Position { x, y } })
     }
 }
@@ -0,0 +1,57 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while talking to the engine.
+#[derive(Debug)]
+pub enum HltError {
+    /// The engine closed the connection, usually because the game is over.
+    UnexpectedEof,
+    /// Reading from the engine failed.
+    Io(io::Error),
+    /// A line of a frame had fewer tokens than expected.
+    MissingToken,
+    /// A token couldn't be parsed as a number.
+    InvalidNumber(String),
+    /// The constants line couldn't be parsed at all.
+    InvalidConstants(String),
+    /// The engine didn't send a constant.
+    MissingConstant(String),
+    /// The engine sent a constant, but its value couldn't be parsed.
+    InvalidConstant { key: String, value: String },
+    /// A frame referenced a player id that isn't in the game.
+    UnknownPlayer(usize),
+    /// A frame referenced a cell that isn't on the map.
+    PositionOutOfMap { x: usize, y: usize },
+    /// A command that isn't in the engine's wire format.
+    InvalidCommand(String),
+    /// A replay file isn't in the format of the official engine.
+    InvalidReplay(String),
+}
+
+impl fmt::Display for HltError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HltError::UnexpectedEof => write!(f, "Input connection from server closed."),
+            HltError::Io(error) => write!(f, "Couldn't read from server: {}", error),
+            HltError::MissingToken => write!(f, "Expected another token in the line from server."),
+            HltError::InvalidNumber(token) => write!(f, "Can't parse '{}'.", token),
+            HltError::InvalidConstants(message) => write!(f, "Error: constants: {}", message),
+            HltError::MissingConstant(key) => write!(f, "Error: constants: server did not send {} constant.", key),
+            HltError::InvalidConstant { key, value } =>
+                write!(f, "Error: constants: for {} got '{}' from server and failed to parse that.", key, value),
+            HltError::UnknownPlayer(id) => write!(f, "Server sent data for unknown player {}.", id),
+            HltError::PositionOutOfMap { x, y } => write!(f, "Server sent data for cell {} {} outside the map.", x, y),
+            HltError::InvalidCommand(command) => write!(f, "Invalid command '{}'.", command),
+            HltError::InvalidReplay(message) => write!(f, "Invalid replay: {}", message),
+        }
+    }
+}
+
+impl Error for HltError {}
+
+impl From<io::Error> for HltError {
+    fn from(error: io::Error) -> HltError {
+        HltError::Io(error)
+    }
+}
@@ -2,6 +2,7 @@ use hlt::command::Command;
 use hlt::constants::Constants;
 use hlt::dropoff::Dropoff;
 use hlt::DropoffId;
+use hlt::error::HltError;
 use hlt::game_map::GameMap;
 use hlt::input::Input;
 use hlt::log::Log;
@@ -12,10 +13,16 @@ use hlt::ship::Ship;
 use hlt::ShipId;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::ops::DerefMut;
+use std::io;
+use std::io::stdin;
+use std::io::BufRead;
+use std::io::StdinLock;
+use std::io::Write;
 use std::rc::Rc;
 
-pub struct Game {
+/// The game state as seen by one bot. By default, the frames
+/// are read from stdin, but any other reader works as well.
+pub struct Game<R: BufRead = StdinLock<'static>> {
     pub log: Rc<RefCell<Log>>,
     pub constants: Constants,
     pub turn_number: usize,
@@ -24,29 +31,63 @@ pub struct Game {
     pub ships: HashMap<ShipId, Ship>,
     pub dropoffs: HashMap<DropoffId, Dropoff>,
     pub game_map: GameMap,
-    input: Input,
+    input: Input<R>,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        let log = Rc::new(RefCell::new(Log::new()));
-        let mut input = Input::new(&log);
-        let constants = Constants::new(log.borrow_mut().deref_mut(), &input.read_and_return_line());
+    /// Reads the initial game state from stdin.
+    pub fn new() -> Result<Game, HltError> {
+        Game::from_reader(stdin().lock())
+    }
+
+    /// Sends the bot's name to the engine on stdout.
+    pub fn ready(name: &str) -> io::Result<()> {
+        Game::ready_to(name, &mut io::stdout())
+    }
 
-        input.read_and_parse_line();
-        let num_players = input.next_usize();
-        let my_id = PlayerId(input.next_usize());
+    /// Sends the commands to the engine on stdout.
+    pub fn end_turn(commands: &[Command]) -> io::Result<()> {
+        Game::end_turn_to(commands, &mut io::stdout())
+    }
+
+    pub fn ready_to<W: Write>(name: &str, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{}", name)?;
+        writer.flush()
+    }
 
-        log.borrow_mut().open(my_id.0);
+    pub fn end_turn_to<W: Write>(commands: &[Command], writer: &mut W) -> io::Result<()> {
+        for command in commands {
+            write!(writer, "{} ", command)?;
+        }
+        writeln!(writer)?;
+        writer.flush()
+    }
+}
+
+impl<R: BufRead> Game<R> {
+    /// Reads the initial game state from the reader.
+    /// Fails if the reader ends before the whole state was read.
+    /// The log keeps everything in memory until it is opened.
+    pub fn from_reader(reader: R) -> Result<Game<R>, HltError> {
+        let log = Rc::new(RefCell::new(Log::new()));
+        let mut input = Input::new(reader);
+        let constants = Constants::new(&input.read_and_return_line()?)?;
+
+        input.read_and_parse_line()?;
+        let num_players = input.next_usize()?;
+        let my_id = PlayerId(input.next_usize()?);
+        if my_id.0 >= num_players {
+            return Err(HltError::UnknownPlayer(my_id.0));
+        }
 
         let mut players: Vec<Player> = Vec::new();
         for _ in 0..num_players {
-            players.push(Player::generate(&mut input));
+            players.push(Player::generate(&mut input)?);
         }
 
-        let game_map = GameMap::generate(&mut input);
+        let game_map = GameMap::generate(&mut input)?;
 
-        Game {
+        Ok(Game {
             log,
             constants,
             turn_number: 0,
@@ -56,18 +97,15 @@ impl Game {
             dropoffs: HashMap::new(),
             game_map,
             input
-        }
-    }
-
-    pub fn ready(name: &str) {
-        println!("{}", name);
+        })
     }
 
-    pub fn update_frame(&mut self) {
+    /// Reads the next frame. Fails with `UnexpectedEof` when the game is over.
+    pub fn update_frame(&mut self) -> Result<(), HltError> {
         let input = &mut self.input;
 
-        input.read_and_parse_line();
-        self.turn_number = input.next_usize();
+        input.read_and_parse_line()?;
+        self.turn_number = input.next_usize()?;
 
         self.log.borrow_mut().log(&format!("=============== TURN {} ================", self.turn_number));
 
@@ -75,24 +113,52 @@ impl Game {
         self.dropoffs.clear();
 
         for _ in 0..self.players.len() {
-            input.read_and_parse_line();
-            let current_player_id = input.next_usize();
-            let num_ships = input.next_usize();
-            let num_dropoffs = input.next_usize();
-            let halite = input.next_usize();
+            input.read_and_parse_line()?;
+            let current_player_id = input.next_usize()?;
+            let num_ships = input.next_usize()?;
+            let num_dropoffs = input.next_usize()?;
+            let halite = input.next_usize()?;
 
-            self.players[current_player_id].update(
+            let player = match self.players.get_mut(current_player_id) {
+                Some(player) => player,
+                None => return Err(HltError::UnknownPlayer(current_player_id)),
+            };
+            player.update(
                 input,
                 self.constants.max_halite,
                 &mut self.ships,
                 &mut self.dropoffs,
                 num_ships,
                 num_dropoffs,
-                halite);
+                halite)?;
         }
 
-        self.game_map.update(input);
+        self.game_map.update(input)?;
+        self.mark_entities();
+        Ok(())
+    }
 
+    /// Builds a game from a known state instead of reading it, e.g. from a replay.
+    /// Ships and dropoffs can be filled in afterwards, followed by `mark_entities`.
+    pub fn from_state(
+        reader: R, constants: Constants, my_id: PlayerId, players: Vec<Player>, game_map: GameMap
+    ) -> Game<R> {
+        Game {
+            log: Rc::new(RefCell::new(Log::new())),
+            constants,
+            turn_number: 0,
+            my_id,
+            players,
+            ships: HashMap::new(),
+            dropoffs: HashMap::new(),
+            game_map,
+            input: Input::new(reader),
+        }
+    }
+
+    /// Marks the ships and structures of all players on the map.
+    pub fn mark_entities(&mut self) {
+        self.game_map.clear_ships();
         for player in &self.players {
             for ship_id in &player.ship_ids {
                 let ship = &self.ships[ship_id];
@@ -108,11 +174,128 @@ impl Game {
             }
         }
     }
+}
 
-    pub fn end_turn(commands: &[Command]) {
-        for command in commands {
-            print!("{} ", command.0);
-        }
-        println!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::position::Position;
+
+    /// The start of a two player game on a 4 by 3 map, as the engine sends it.
+    const TRANSCRIPT: &str = "\
+{\"CAPTURE_ENABLED\":false,\"CAPTURE_RADIUS\":3,\"DEFAULT_MAP_HEIGHT\":32,\"DEFAULT_MAP_WIDTH\":32,\"DROPOFF_COST\":4000,\"DROPOFF_PENALTY_RATIO\":4,\"EXTRACT_RATIO\":4,\"FACTOR_EXP_1\":2.0,\"FACTOR_EXP_2\":2.0,\"INITIAL_ENERGY\":5000,\"INSPIRATION_ENABLED\":true,\"INSPIRATION_RADIUS\":4,\"INSPIRATION_SHIP_COUNT\":2,\"INSPIRED_BONUS_MULTIPLIER\":2.0,\"INSPIRED_EXTRACT_RATIO\":4,\"INSPIRED_MOVE_COST_RATIO\":10,\"MAX_CELL_PRODUCTION\":1000,\"MAX_ENERGY\":1000,\"MAX_PLAYERS\":16,\"MAX_TURNS\":401,\"MAX_TURN_THRESHOLD\":64,\"MIN_CELL_PRODUCTION\":900,\"MIN_TURNS\":400,\"MIN_TURN_THRESHOLD\":32,\"MOVE_COST_RATIO\":10,\"NEW_ENTITY_ENERGY_COST\":1000,\"PERSISTENCE\":0.7,\"SHIPS_ABOVE_FOR_CAPTURE\":3,\"STRICT_ERRORS\":false,\"game_seed\":1542409934}
+2 0
+0 0 1
+1 3 1
+4 3
+10 20 30 40
+0 0 0 0
+50 60 70 80
+1
+0 1 0 4000
+0 0 1 0
+1 0 0 5000
+0
+2
+0 1 0 4000
+0 0 0 3
+1 1 1 4000
+1 3 0 0
+0 2 2
+2
+0 0 7
+2 2 0
+";
+
+    fn game(transcript: &str) -> Result<Game<&[u8]>, HltError> {
+        Game::from_reader(transcript.as_bytes())
+    }
+
+    #[test]
+    fn a_transcript_plays_back_frame_by_frame() {
+        let mut game = game(TRANSCRIPT).unwrap();
+        assert_eq!(game.constants.max_turns, 401);
+        assert_eq!(game.my_id, PlayerId(0));
+        assert_eq!(game.players.len(), 2);
+        assert_eq!(game.players[1].shipyard.position, Position { x: 3, y: 1 });
+        assert_eq!((game.game_map.width, game.game_map.height), (4, 3));
+        assert_eq!(game.game_map.at_position(&Position { x: 3, y: 2 }).halite, 80);
+
+        game.update_frame().unwrap();
+        assert_eq!(game.turn_number, 1);
+        assert_eq!(game.players[0].halite, 4000);
+        assert_eq!(game.players[0].ship_ids, vec![ShipId(0)]);
+        assert_eq!(game.ships[&ShipId(0)].position, Position { x: 0, y: 1 });
+        assert!(game.game_map.at_position(&Position { x: 0, y: 1 }).is_occupied());
+
+        game.update_frame().unwrap();
+        assert_eq!(game.turn_number, 2);
+        let ship = &game.ships[&ShipId(0)];
+        assert_eq!((ship.position, ship.halite), (Position { x: 0, y: 0 }, 3));
+        assert_eq!(game.players[1].ship_ids, vec![ShipId(1)]);
+        assert_eq!(game.dropoffs[&DropoffId(0)].owner, PlayerId(1));
+        assert!(game.game_map.at_position(&Position { x: 2, y: 2 }).structure == Structure::Dropoff(DropoffId(0)));
+        assert_eq!(game.game_map.at_position(&Position { x: 0, y: 0 }).halite, 7);
+        assert_eq!(game.game_map.at_position(&Position { x: 2, y: 2 }).halite, 0);
+        assert!(!game.game_map.at_position(&Position { x: 0, y: 1 }).is_occupied());
+
+        // The engine closed the connection.
+        assert!(matches!(game.update_frame(), Err(HltError::UnexpectedEof)));
+    }
+
+    /// The transcript with the first `from` replaced.
+    fn changed(from: &str, to: &str) -> String {
+        assert!(TRANSCRIPT.contains(from));
+        TRANSCRIPT.replacen(from, to, 1)
+    }
+
+    #[test]
+    fn my_id_must_be_one_of_the_players() {
+        assert!(matches!(game(&changed("2 0\n", "2 2\n")), Err(HltError::UnknownPlayer(2))));
+    }
+
+    #[test]
+    fn a_frame_for_an_unknown_player_is_an_error() {
+        let transcript = changed("1 0 0 5000", "5 0 0 5000");
+        let mut game = game(&transcript).unwrap();
+        assert!(matches!(game.update_frame(), Err(HltError::UnknownPlayer(5))));
+    }
+
+    #[test]
+    fn garbage_is_a_parse_error() {
+        assert!(matches!(game(&changed("2 0\n", "2 zero\n")), Err(HltError::InvalidNumber(ref token)) if token == "zero"));
+        assert!(matches!(game(&changed("4 3\n", "4\n")), Err(HltError::MissingToken)));
+        assert!(matches!(game(&changed("{", "[")), Err(HltError::InvalidConstants(_))));
+        assert!(matches!(game(&changed("\"MAX_ENERGY\":1000", "\"MAX_ENERGY\":\"lots\"")),
+            Err(HltError::InvalidConstant { ref key, .. }) if key == "MAX_ENERGY"));
+
+        let transcript = changed("0 0 1 0\n", "0 0 1 -5\n");
+        let mut game = game(&transcript).unwrap();
+        assert!(matches!(game.update_frame(), Err(HltError::InvalidNumber(ref token)) if token == "-5"));
+    }
+
+    #[test]
+    fn a_change_outside_the_map_is_an_error() {
+        let transcript = changed("2 2 0\n", "4 2 0\n");
+        let mut game = game(&transcript).unwrap();
+        game.update_frame().unwrap();
+        assert!(matches!(game.update_frame(), Err(HltError::PositionOutOfMap { x: 4, y: 2 })));
+    }
+
+    #[test]
+    fn no_input_at_all_is_unexpected_eof() {
+        assert!(matches!(game(""), Err(HltError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn a_truncated_transcript_ends_with_unexpected_eof() {
+        let map_cut = TRANSCRIPT.find("0 0 0 0").unwrap();
+        assert!(matches!(game(&TRANSCRIPT[..map_cut]), Err(HltError::UnexpectedEof)));
+
+        // In the middle of the ships of the second frame.
+        let frame_cut = TRANSCRIPT.find("1 3 0 0").unwrap();
+        let mut game = game(&TRANSCRIPT[..frame_cut]).unwrap();
+        game.update_frame().unwrap();
+        assert!(matches!(game.update_frame(), Err(HltError::UnexpectedEof)));
     }
 }
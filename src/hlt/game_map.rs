@@ -1,80 +1,194 @@
 use hlt::direction::Direction;
 use hlt::entity::Entity;
+use hlt::error::HltError;
 use hlt::input::Input;
 use hlt::map_cell::MapCell;
 use hlt::map_cell::Structure;
 use hlt::position::Position;
 use hlt::ship::Ship;
+use std::cmp::max;
 use std::cmp::min;
+use std::io::BufRead;
+use std::ops::RangeInclusive;
 
+/// Index of a cell in the flat grid, `y * width + x` of the normalized position.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CellIndex(pub usize);
+
+/// The halite of a cell before and after the last update.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CellChange {
+    pub index: CellIndex,
+    pub previous: usize,
+    pub halite: usize,
+}
+
+impl CellChange {
+    /// Negative if the cell was mined, positive if cargo was dropped there.
+    pub fn delta(&self) -> i64 {
+        self.halite as i64 - self.previous as i64
+    }
+}
+
+/// The cells are stored row by row in one `Vec`, so a position is
+/// normalized once and everything else works with `CellIndex`.
+#[derive(Clone)]
 pub struct GameMap {
     pub width: usize,
     pub height: usize,
-    cells: Vec<Vec<MapCell>>,
+    cells: Vec<MapCell>,
+    /// The cardinal neighbours of every cell, in the order of `Direction`:
+    /// North, East, South, West.
+    neighbours: Vec<[CellIndex; 4]>,
+    /// The cells `set_halite` changed since `clear_changes`, in no particular order.
+    changes: Vec<CellChange>,
+    /// Where the change of every cell is in `changes`, indexed by `CellIndex`.
+    change_of: Vec<Option<usize>>,
 }
 
 impl GameMap {
+    /// Builds a map without structures or ships from
+    /// the halite of every cell, given row by row.
+    pub fn new(width: usize, height: usize, halite: &[usize]) -> GameMap {
+        assert_eq!(halite.len(), width * height);
+
+        let cells = halite.iter()
+            .map(|&halite| MapCell { halite, ship: None, structure: Structure::None })
+            .collect();
+        GameMap {
+            width,
+            height,
+            cells,
+            neighbours: GameMap::neighbour_table(width, height),
+            changes: Vec::new(),
+            change_of: vec![None; width * height],
+        }
+    }
+
+    fn neighbour_table(width: usize, height: usize) -> Vec<[CellIndex; 4]> {
+        let mut neighbours = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let north = (y + height - 1) % height;
+                let south = (y + 1) % height;
+                let east = (x + 1) % width;
+                let west = (x + width - 1) % width;
+                neighbours.push([
+                    CellIndex(north * width + x),
+                    CellIndex(y * width + east),
+                    CellIndex(south * width + x),
+                    CellIndex(y * width + west),
+                ]);
+            }
+        }
+        neighbours
+    }
+
     pub fn at_position(&self, position: &Position) -> &MapCell {
-        let normalized = self.normalize(position);
-        &self.cells[normalized.y as usize][normalized.x as usize]
+        &self.cells[self.index_of(position).0]
     }
 
     pub fn at_position_mut(&mut self, position: &Position) -> &mut MapCell {
-        let normalized = self.normalize(position);
-        &mut self.cells[normalized.y as usize][normalized.x as usize]
+        let index = self.index_of(position);
+        &mut self.cells[index.0]
     }
 
-    pub fn at_entity(&self, entity: &Entity) -> &MapCell {
+    pub fn at_entity(&self, entity: &dyn Entity) -> &MapCell {
         self.at_position(&entity.position())
     }
 
-    pub fn at_entity_mut(&mut self, entity: &Entity) -> &mut MapCell {
+    pub fn at_entity_mut(&mut self, entity: &dyn Entity) -> &mut MapCell {
         self.at_position_mut(&entity.position())
     }
 
-    pub fn calculate_distance(&self, source: &Position, target: &Position) -> usize {
-        let normalized_source = self.normalize(source);
-        let normalized_target = self.normalize(target);
+    pub fn cell(&self, index: CellIndex) -> &MapCell {
+        &self.cells[index.0]
+    }
 
-        let dx = (normalized_source.x - normalized_target.x).abs() as usize;
-        let dy = (normalized_source.y - normalized_target.y).abs() as usize;
+    pub fn cell_mut(&mut self, index: CellIndex) -> &mut MapCell {
+        &mut self.cells[index.0]
+    }
 
-        let toroidal_dx = min(dx, self.width - dx);
-        let toroidal_dy = min(dy, self.height - dy);
+    /// Works with positions outside of the map, too.
+    pub fn index_of(&self, position: &Position) -> CellIndex {
+        let wrapped = position.wrap(self.width, self.height);
+        CellIndex(wrapped.y as usize * self.width + wrapped.x as usize)
+    }
 
-        return toroidal_dx + toroidal_dy;
+    /// The normalized position of the cell.
+    pub fn position_of(&self, index: CellIndex) -> Position {
+        Position { x: (index.0 % self.width) as i32, y: (index.0 / self.width) as i32 }
     }
 
-    pub fn normalize(&self, position: &Position) -> Position {
-        let width = self.width as i32;
-        let height = self.height as i32;
-        let x = ((position.x % width) + width) % width;
-        let y = ((position.y % height) + height) % height;
-        Position { x, y }
+    pub fn neighbour(&self, index: CellIndex, direction: Direction) -> CellIndex {
+        match direction {
+            Direction::North => self.neighbours[index.0][0],
+            Direction::East => self.neighbours[index.0][1],
+            Direction::South => self.neighbours[index.0][2],
+            Direction::West => self.neighbours[index.0][3],
+            Direction::Still => index,
+        }
     }
 
-    pub fn get_unsafe_moves(&self, source: &Position, destination: &Position) -> Vec<Direction> {
-        let normalized_source = self.normalize(source);
-        let normalized_destination = self.normalize(destination);
+    /// North, East, South and West of the cell.
+    pub fn neighbours(&self, index: CellIndex) -> [CellIndex; 4] {
+        self.neighbours[index.0]
+    }
+
+    /// All cells, row by row.
+    pub fn indices(&self) -> impl Iterator<Item = CellIndex> {
+        (0..self.cells.len()).map(CellIndex)
+    }
 
-        let dx = (normalized_source.x - normalized_destination.x).abs() as usize;
-        let dy = (normalized_source.y - normalized_destination.y).abs() as usize;
+    /// Every cell with a `calculate_distance` of at most `radius`, each of them once,
+    /// even if the radius is bigger than half the map.
+    pub fn within_radius<'map>(
+        &'map self, position: &Position, radius: usize
+    ) -> impl Iterator<Item = CellIndex> + 'map {
+        let center = *position;
+        let radius = radius as i32;
+        GameMap::offsets(self.height, radius)
+            .flat_map(move |dy| {
+                let remaining = radius - dy.abs();
+                GameMap::offsets(self.width, remaining)
+                    .map(move |dx| self.index_of(&Position { x: center.x + dx, y: center.y + dy }))
+            })
+    }
 
-        let wrapped_dx = self.width - dx;
-        let wrapped_dy = self.height - dy;
+    /// The offsets from -radius to radius, but only one of those that wrap to the same cell.
+    fn offsets(size: usize, radius: i32) -> RangeInclusive<i32> {
+        let size = size as i32;
+        max(-radius, -(size - 1) / 2)..=min(radius, size / 2)
+    }
+
+    pub fn calculate_distance(&self, source: &Position, target: &Position) -> usize {
+        self.delta(source, target).manhattan_length()
+    }
+
+    /// The shortest offset from `source` to `target`, see `Position::torus_delta`.
+    pub fn delta(&self, source: &Position, target: &Position) -> Position {
+        source.torus_delta(target, self.width, self.height)
+    }
+
+    pub fn normalize(&self, position: &Position) -> Position {
+        position.wrap(self.width, self.height)
+    }
 
+    /// If both ways around the map are equally long, East and South are taken.
+    pub fn get_unsafe_moves(&self, source: &Position, destination: &Position) -> Vec<Direction> {
+        let delta = self.delta(source, destination);
         let mut possible_moves: Vec<Direction> = Vec::new();
 
-        if normalized_source.x < normalized_destination.x {
-            possible_moves.push(if dx > wrapped_dx { Direction::West } else { Direction::East });
-        } else if normalized_source.x > normalized_destination.x {
-            possible_moves.push(if dx < wrapped_dx { Direction::West } else { Direction::East });
+        if delta.x > 0 {
+            possible_moves.push(Direction::East);
+        } else if delta.x < 0 {
+            possible_moves.push(Direction::West);
         }
 
-        if normalized_source.y < normalized_destination.y {
-            possible_moves.push(if dy > wrapped_dy { Direction::North } else { Direction::South });
-        } else if normalized_source.y > normalized_destination.y {
-            possible_moves.push(if dy < wrapped_dy { Direction::North } else { Direction::South });
+        if delta.y > 0 {
+            possible_moves.push(Direction::South);
+        } else if delta.y < 0 {
+            possible_moves.push(Direction::North);
         }
 
         possible_moves
@@ -84,7 +198,7 @@ impl GameMap {
         let ship_position = &ship.position;
 
         // get_unsafe_moves normalizes for us
-        for direction in self.get_unsafe_moves(&ship_position, destination) {
+        for direction in self.get_unsafe_moves(ship_position, destination) {
             let target_pos = ship_position.directional_offset(direction);
             let target_cell = self.at_position_mut(&target_pos);
 
@@ -97,47 +211,169 @@ impl GameMap {
         Direction::Still
     }
 
-    pub fn update(&mut self, input: &mut Input) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                self.cells[y][x].ship = None;
+    pub fn clear_ships(&mut self) {
+        for cell in &mut self.cells {
+            cell.ship = None;
+        }
+    }
+
+    /// Changes the halite of the cell and remembers what it was before.
+    /// Changes through `at_position_mut` and `cell_mut` aren't tracked.
+    pub fn set_halite(&mut self, index: CellIndex, halite: usize) {
+        let previous = self.cells[index.0].halite;
+        self.cells[index.0].halite = halite;
+
+        match self.change_of[index.0] {
+            Some(position) => {
+                self.changes[position].halite = halite;
+                if self.changes[position].previous == halite {
+                    self.changes.swap_remove(position);
+                    self.change_of[index.0] = None;
+                    if let Some(moved) = self.changes.get(position) {
+                        self.change_of[moved.index.0] = Some(position);
+                    }
+                }
+            }
+            None => if previous != halite {
+                self.change_of[index.0] = Some(self.changes.len());
+                self.changes.push(CellChange { index, previous, halite });
             }
         }
+    }
+
+    /// The cells whose halite changed in the last update, e.g. by mining,
+    /// shipwrecks or dropoffs. The engine only sends those.
+    pub fn changes(&self) -> &[CellChange] {
+        &self.changes
+    }
+
+    pub fn clear_changes(&mut self) {
+        for change in &self.changes {
+            self.change_of[change.index.0] = None;
+        }
+        self.changes.clear();
+    }
+
+    pub fn update<R: BufRead>(&mut self, input: &mut Input<R>) -> Result<(), HltError> {
+        self.clear_ships();
+        self.clear_changes();
 
-        input.read_and_parse_line();
-        let update_count = input.next_usize();
+        input.read_and_parse_line()?;
+        let update_count = input.next_usize()?;
 
         for _ in 0..update_count {
-            input.read_and_parse_line();
-            let x = input.next_usize();
-            let y = input.next_usize();
-            let halite = input.next_usize();
+            input.read_and_parse_line()?;
+            let x = input.next_usize()?;
+            let y = input.next_usize()?;
+            let halite = input.next_usize()?;
+            if x >= self.width || y >= self.height {
+                return Err(HltError::PositionOutOfMap { x, y });
+            }
 
-            self.cells[y][x].halite = halite;
+            self.set_halite(CellIndex(y * self.width + x), halite);
         }
+        Ok(())
     }
 
-    pub fn generate(input: &mut Input) -> GameMap {
-        input.read_and_parse_line();
-        let width = input.next_usize();
-        let height = input.next_usize();
+    pub fn generate<R: BufRead>(input: &mut Input<R>) -> Result<GameMap, HltError> {
+        input.read_and_parse_line()?;
+        let width = input.next_usize()?;
+        let height = input.next_usize()?;
 
-        let mut cells: Vec<Vec<MapCell>> = Vec::with_capacity(height);
-        for y in 0..height {
-            input.read_and_parse_line();
+        let mut halite = Vec::with_capacity(width * height);
+        for _ in 0..height {
+            input.read_and_parse_line()?;
+            for _ in 0..width {
+                halite.push(input.next_usize()?);
+            }
+        }
 
-            let mut row: Vec<MapCell> = Vec::with_capacity(width);
-            for x in 0..width {
-                let halite = input.next_usize();
+        Ok(GameMap::new(width, height, &halite))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_changes(game_map: &GameMap) -> Vec<(usize, usize, usize)> {
+        let mut changes: Vec<(usize, usize, usize)> = game_map.changes().iter()
+            .map(|change| (change.index.0, change.previous, change.halite))
+            .collect();
+        changes.sort();
+        changes
+    }
+
+    #[test]
+    fn index_of_wraps_any_position() {
+        let game_map = GameMap::new(5, 3, &[0; 15]);
+        assert_eq!(game_map.index_of(&Position { x: 2, y: 1 }), CellIndex(7));
+        assert_eq!(game_map.index_of(&Position { x: -1, y: 0 }), CellIndex(4));
+        assert_eq!(game_map.index_of(&Position { x: 5, y: -1 }), CellIndex(10));
+        assert_eq!(game_map.index_of(&Position { x: -11, y: 7 }), CellIndex(9));
+        for index in game_map.indices() {
+            assert_eq!(game_map.index_of(&game_map.position_of(index)), index);
+        }
+        assert_eq!(game_map.indices().count(), 15);
+    }
 
-                let position = Position { x: x as i32, y: y as i32 };
-                let cell = MapCell { position, halite, ship: None, structure: Structure::None };
-                row.push(cell);
+    #[test]
+    fn neighbours_wrap_around_the_edges() {
+        let game_map = GameMap::new(5, 3, &[0; 15]);
+        let corner = game_map.index_of(&Position { x: 0, y: 0 });
+        assert_eq!(game_map.neighbours(corner), [CellIndex(10), CellIndex(1), CellIndex(5), CellIndex(4)]);
+        assert_eq!(game_map.neighbour(corner, Direction::Still), corner);
+
+        for index in game_map.indices() {
+            let position = game_map.position_of(index);
+            for direction in Direction::get_all_cardinals() {
+                assert_eq!(game_map.neighbour(index, direction),
+                    game_map.index_of(&position.directional_offset(direction)));
             }
+        }
+    }
+
+    #[test]
+    fn within_radius_has_every_close_cell_once() {
+        for &(width, height, radius) in &[(9, 9, 2), (8, 6, 3), (3, 2, 5), (4, 4, 2), (1, 1, 3), (6, 1, 4)] {
+            let game_map = GameMap::new(width, height, &vec![0; width * height]);
+            for center in game_map.indices().map(|index| game_map.position_of(index)) {
+                let mut cells: Vec<CellIndex> = game_map.within_radius(&center, radius).collect();
+                let count = cells.len();
+                cells.sort_by_key(|index| index.0);
+                cells.dedup();
+                assert_eq!(cells.len(), count, "duplicates on {}x{} with radius {}", width, height, radius);
 
-            cells.push(row);
+                let expected: Vec<CellIndex> = game_map.indices()
+                    .filter(|&index| game_map.calculate_distance(&center, &game_map.position_of(index)) <= radius)
+                    .collect();
+                assert_eq!(cells, expected, "{}x{} with radius {}", width, height, radius);
+            }
         }
+    }
+
+    #[test]
+    fn set_halite_keeps_one_change_per_cell() {
+        let mut game_map = GameMap::new(4, 4, &[100; 16]);
+        game_map.set_halite(CellIndex(3), 75);
+        game_map.set_halite(CellIndex(5), 100);
+        game_map.set_halite(CellIndex(7), 150);
+        game_map.set_halite(CellIndex(3), 50);
+        assert_eq!(sorted_changes(&game_map), vec![(3, 100, 50), (7, 100, 150)]);
+        assert_eq!(game_map.changes()[0].delta() + game_map.changes()[1].delta(), 0);
+
+        // Back to where it was, so it didn't change after all.
+        game_map.set_halite(CellIndex(3), 100);
+        assert_eq!(sorted_changes(&game_map), vec![(7, 100, 150)]);
+        game_map.set_halite(CellIndex(9), 0);
+        game_map.set_halite(CellIndex(7), 100);
+        game_map.set_halite(CellIndex(9), 1);
+        assert_eq!(sorted_changes(&game_map), vec![(9, 100, 1)]);
 
-        GameMap { width, height, cells }
+        game_map.clear_changes();
+        assert!(game_map.changes().is_empty());
+        game_map.set_halite(CellIndex(9), 100);
+        assert_eq!(sorted_changes(&game_map), vec![(9, 1, 100)]);
+        assert_eq!(game_map.cell(CellIndex(9)).halite, 100);
     }
 }
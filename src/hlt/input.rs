@@ -1,59 +1,54 @@
-use hlt::log::Log;
-use std::cell::RefCell;
-use std::io::stdin;
-use std::process::exit;
-use std::rc::Rc;
+use hlt::error::HltError;
+use std::io::BufRead;
 use std::str::FromStr;
 
-pub struct Input {
-    log: Rc<RefCell<Log>>,
+/// Reads the engine's frames line by line from any reader,
+/// e.g. stdin, a recorded transcript or a simulator.
+pub struct Input<R: BufRead> {
+    reader: R,
     tokens: Vec<String>,
     current_token: usize,
 }
 
-impl Input {
-    pub fn new(log: &Rc<RefCell<Log>>) -> Input {
-        Input { log: log.clone(), tokens: Vec::new(), current_token: 0 }
+impl<R: BufRead> Input<R> {
+    pub fn new(reader: R) -> Input<R> {
+        Input { reader, tokens: Vec::new(), current_token: 0 }
     }
 
-    pub fn read_and_return_line(&mut self) -> String {
+    /// Returns `HltError::UnexpectedEof` when the engine closed the connection.
+    pub fn read_and_return_line(&mut self) -> Result<String, HltError> {
         let mut buf = String::new();
-        match stdin().read_line(&mut buf) {
-            Ok(_) => (),
-            Err(_) => {
-                self.log.borrow_mut().log("Input connection from server closed. Exiting...");
-                self.log.borrow_mut().flush();
-                exit(0);
-            },
+        if self.reader.read_line(&mut buf)? == 0 {
+            return Err(HltError::UnexpectedEof);
         }
-        buf
+        Ok(buf)
     }
 
-    pub fn read_and_parse_line(&mut self) {
-        let buf = self.read_and_return_line();
+    pub fn read_and_parse_line(&mut self) -> Result<(), HltError> {
+        let buf = self.read_and_return_line()?;
         let token_iter = buf.split_whitespace();
         let token_iter = token_iter.filter(|x| !x.is_empty());
         let token_iter = token_iter.map(|x| x.to_string());
         self.tokens = token_iter.collect();
         self.current_token = 0;
+        Ok(())
     }
 
-    pub fn next_i32(&mut self) -> i32 {
+    pub fn next_i32(&mut self) -> Result<i32, HltError> {
         self.next()
     }
 
-    pub fn next_usize(&mut self) -> usize {
+    pub fn next_usize(&mut self) -> Result<usize, HltError> {
         self.next()
     }
 
-    pub fn next<T: FromStr>(&mut self) -> T {
-        let token = &self.tokens[self.current_token];
+    pub fn next<T: FromStr>(&mut self) -> Result<T, HltError> {
+        let token = match self.tokens.get(self.current_token) {
+            Some(token) => token,
+            None => return Err(HltError::MissingToken),
+        };
         self.current_token += 1;
 
-        let result = match token.parse() {
-            Ok(x) => x,
-            Err(_) => self.log.borrow_mut().panic(&format!("Can't parse '{}'.", token)),
-        };
-        result
+        token.parse().map_err(|_| HltError::InvalidNumber(token.clone()))
     }
 }
@@ -1,6 +1,5 @@
 use std::fs::File;
 use std::io::Write;
-use std::process::exit;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
@@ -9,6 +8,12 @@ pub struct Log {
     file: Option<File>,
 }
 
+impl Default for Log {
+    fn default() -> Log {
+        Log::new()
+    }
+}
+
 impl Log {
     pub fn new() -> Log {
         Log { log_buffer: Some(Vec::new()), file: None }
@@ -16,11 +21,12 @@ impl Log {
 
     pub fn open(&mut self, bot_id: usize) {
         if self.file.is_some() {
-            self.panic(&format!("Error: log: tried to open({}) but we have already opened before.", bot_id));
+            self.error(&format!("Error: log: tried to open({}) but we have already opened before.", bot_id));
+            return;
         }
 
         let filename = format!("bot-{}.log", bot_id);
-        let file = File::create(&filename).expect(&format!("Couldn't open file {} for logging!", &filename));
+        let file = File::create(&filename).unwrap_or_else(|_| panic!("Couldn't open file {} for logging!", &filename));
         self.file = Some(file);
 
         self.dump_log_buffer();
@@ -29,12 +35,9 @@ impl Log {
     }
 
     pub fn log(&mut self, message: &str) {
-        match &mut self.file {
-            Some(file) => {
-                writeln!(file, "{}", message).unwrap();
-                return;
-            },
-            None => ()
+        if let Some(file) = &mut self.file {
+            writeln!(file, "{}", message).unwrap();
+            return;
         }
 
         match &mut self.log_buffer {
@@ -46,21 +49,23 @@ impl Log {
     }
 
     pub fn flush(&mut self) {
-        match &mut self.file {
-            Some(file) => { file.flush().unwrap(); },
-            None => (),
+        if let Some(file) = &mut self.file {
+            file.flush().unwrap();
         }
     }
 
-    pub fn panic(&mut self, message: &str) -> ! {
+    /// Writes the message and everything buffered so far to a file,
+    /// even if the log wasn't opened yet. The caller decides whether to go on.
+    pub fn error(&mut self, message: &str) {
         if self.file.is_none() {
             let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
             let filename = format!("bot-unknown-{}.log", timestamp.as_secs());
-            let file = File::create(&filename).expect(&format!("Couldn't open file {} for logging!", &filename));
+            let file = File::create(&filename).unwrap_or_else(|_| panic!("Couldn't open file {} for logging!", &filename));
             self.file = Some(file);
         }
 
         self.dump_log_buffer();
+        self.log_buffer = None;
 
         let file = match &mut self.file {
             Some(file) => file,
@@ -69,8 +74,6 @@ impl Log {
 
         writeln!(file, "{}", message).unwrap();
         file.flush().unwrap();
-
-        exit(1);
     }
 
     fn dump_log_buffer(&mut self) {
@@ -79,13 +82,10 @@ impl Log {
             None => panic!("Error: file should exist!")
         };
 
-        match &self.log_buffer {
-            Some(log_buffer) => {
-                for message in log_buffer {
-                    writeln!(file, "{}", message).unwrap();
-                }
+        if let Some(log_buffer) = &self.log_buffer {
+            for message in log_buffer {
+                writeln!(file, "{}", message).unwrap();
             }
-            None => ()
         }
     }
 }
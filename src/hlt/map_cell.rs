@@ -1,10 +1,9 @@
 use hlt::DropoffId;
-use hlt::position::Position;
 use hlt::ShipId;
 use hlt::PlayerId;
 
+#[derive(Clone)]
 pub struct MapCell {
-    pub position: Position,
     pub halite: usize,
     pub ship: Option<ShipId>,
     pub structure: Structure,
@@ -28,7 +27,7 @@ impl MapCell {
     }
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 pub enum Structure {
     None,
     Dropoff(DropoffId),
@@ -37,10 +36,7 @@ pub enum Structure {
 
 impl Structure {
     pub fn is_some(&self) -> bool {
-        match *self {
-            Structure::None => false,
-            _ => true,
-        }
+        !matches!(*self, Structure::None)
     }
 
     pub fn is_none(&self) -> bool {
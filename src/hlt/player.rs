@@ -1,5 +1,6 @@
 use hlt::dropoff::Dropoff;
 use hlt::DropoffId;
+use hlt::error::HltError;
 use hlt::input::Input;
 use hlt::PlayerId;
 use hlt::position::Position;
@@ -7,7 +8,9 @@ use hlt::ship::Ship;
 use hlt::ShipId;
 use hlt::shipyard::Shipyard;
 use std::collections::HashMap;
+use std::io::BufRead;
 
+#[derive(Clone)]
 pub struct Player {
     pub id: PlayerId,
     pub shipyard: Shipyard,
@@ -17,41 +20,43 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn update(
+    #[allow(clippy::too_many_arguments)]
+    pub fn update<R: BufRead>(
         &mut self,
-        input: &mut Input,
+        input: &mut Input<R>,
         max_halite: usize,
         ships: &mut HashMap<ShipId, Ship>,
         dropoffs: &mut HashMap<DropoffId, Dropoff>,
         num_ships: usize,
         num_dropoffs: usize,
-        halite: usize)
+        halite: usize) -> Result<(), HltError>
     {
         self.halite = halite;
 
         self.ship_ids.clear();
         for _ in 0..num_ships {
-            let ship = Ship::generate(input, self.id, max_halite);
+            let ship = Ship::generate(input, self.id, max_halite)?;
             self.ship_ids.push(ship.id);
             ships.insert(ship.id, ship);
         }
 
         self.dropoff_ids.clear();
         for _ in 0..num_dropoffs {
-            let dropoff = Dropoff::generate(input, self.id);
+            let dropoff = Dropoff::generate(input, self.id)?;
             self.dropoff_ids.push(dropoff.id);
             dropoffs.insert(dropoff.id, dropoff);
         }
+        Ok(())
     }
 
-    pub fn generate(input: &mut Input) -> Player {
-        input.read_and_parse_line();
-        let id = PlayerId(input.next_usize());
-        let shipyard_x = input.next_i32();
-        let shipyard_y = input.next_i32();
+    pub fn generate<R: BufRead>(input: &mut Input<R>) -> Result<Player, HltError> {
+        input.read_and_parse_line()?;
+        let id = PlayerId(input.next_usize()?);
+        let shipyard_x = input.next_i32()?;
+        let shipyard_y = input.next_i32()?;
 
         let shipyard = Shipyard { owner: id, position: Position { x: shipyard_x, y: shipyard_y } };
 
-        Player { id, shipyard, halite: 0, ship_ids: Vec::new(), dropoff_ids: Vec::new() }
+        Ok(Player { id, shipyard, halite: 0, ship_ids: Vec::new(), dropoff_ids: Vec::new() })
     }
 }
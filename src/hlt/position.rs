@@ -1,5 +1,10 @@
 use hlt::direction::Direction;
+use std::ops::Add;
+use std::ops::Neg;
+use std::ops::Sub;
 
+/// A cell on the map, or an offset between two of them.
+/// The arithmetic doesn't wrap, use `wrap` or `GameMap::normalize` for that.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Position {
     pub x: i32,
@@ -25,4 +30,183 @@ impl Position {
             self.directional_offset(Direction::East), self.directional_offset(Direction::West)
         }
     }
+
+    /// The same cell on a map of that size, with 0 <= x < width and 0 <= y < height.
+    pub fn wrap(&self, width: usize, height: usize) -> Position {
+        Position {
+            x: wrap_coordinate(self.x, width),
+            y: wrap_coordinate(self.y, height),
+        }
+    }
+
+    /// The shortest offset from here to `target` on a wrapping map, so that
+    /// `self + delta` is the target again after wrapping. Both coordinates
+    /// are between `-(size - 1) / 2` and `size / 2`.
+    pub fn torus_delta(&self, target: &Position, width: usize, height: usize) -> Position {
+        Position {
+            x: delta_coordinate(self.x, target.x, width),
+            y: delta_coordinate(self.y, target.y, height),
+        }
+    }
+
+    /// The number of moves an offset takes.
+    pub fn manhattan_length(&self) -> usize {
+        (self.x.unsigned_abs() + self.y.unsigned_abs()) as usize
+    }
+
+    /// All positions at most `radius` moves away, from north to south.
+    /// They are not wrapped, so on a small map some are the same cell.
+    /// See `GameMap::within_radius` for unique cells.
+    pub fn within_radius(&self, radius: usize) -> impl Iterator<Item = Position> {
+        let center = *self;
+        let radius = radius as i32;
+        (-radius..=radius).flat_map(move |dy| {
+            let remaining = radius - dy.abs();
+            (-remaining..=remaining).map(move |dx| Position { x: center.x + dx, y: center.y + dy })
+        })
+    }
+
+    /// All positions exactly `radius` moves away, clockwise from the north.
+    pub fn ring(&self, radius: usize) -> impl Iterator<Item = Position> {
+        let center = *self;
+        let radius = radius as i32;
+        let steps = if radius == 0 { 1 } else { 4 * radius };
+        (0..steps).map(move |step| {
+            let side = step / radius.max(1);
+            let along = step % radius.max(1);
+            let (dx, dy) = match side {
+                0 => (along, -radius + along),
+                1 => (radius - along, along),
+                2 => (-along, radius - along),
+                _ => (-radius + along, -along),
+            };
+            Position { x: center.x + dx, y: center.y + dy }
+        })
+    }
+}
+
+/// `coordinate` wrapped into `0..size`.
+pub fn wrap_coordinate(coordinate: i32, size: usize) -> i32 {
+    coordinate.rem_euclid(size as i32)
+}
+
+/// The shortest signed distance from `from` to `to` along one wrapping axis.
+pub fn delta_coordinate(from: i32, to: i32, size: usize) -> i32 {
+    let size = size as i32;
+    let delta = (to - from).rem_euclid(size);
+    if delta > size / 2 { delta - size } else { delta }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, offset: Position) -> Position {
+        Position { x: self.x + offset.x, y: self.y + offset.y }
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    fn sub(self, other: Position) -> Position {
+        Position { x: self.x - other.x, y: self.y - other.y }
+    }
+}
+
+impl Neg for Position {
+    type Output = Position;
+
+    fn neg(self) -> Position {
+        Position { x: -self.x, y: -self.y }
+    }
+}
+
+/// One step in the direction.
+impl Add<Direction> for Position {
+    type Output = Position;
+
+    fn add(self, direction: Direction) -> Position {
+        self.directional_offset(direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn at(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+
+    #[test]
+    fn wrap_brings_any_position_onto_the_map() {
+        assert_eq!(at(3, 2).wrap(8, 4), at(3, 2));
+        assert_eq!(at(-1, -1).wrap(8, 4), at(7, 3));
+        assert_eq!(at(8, 4).wrap(8, 4), at(0, 0));
+        assert_eq!(at(-17, 9).wrap(8, 4), at(7, 1));
+    }
+
+    #[test]
+    fn torus_delta_takes_the_shorter_way() {
+        assert_eq!(at(1, 1).torus_delta(&at(3, 0), 8, 8), at(2, -1));
+        assert_eq!(at(1, 1).torus_delta(&at(7, 7), 8, 8), at(-2, -2));
+        assert_eq!(at(6, 0).torus_delta(&at(1, 0), 8, 8), at(3, 0));
+        for &(from, to) in &[(at(0, 0), at(5, 6)), (at(7, 3), at(2, 2)), (at(4, 4), at(4, 4))] {
+            assert_eq!((from + from.torus_delta(&to, 8, 7)).wrap(8, 7), to);
+        }
+    }
+
+    #[test]
+    fn torus_delta_is_positive_at_exactly_half_the_map() {
+        assert_eq!(at(0, 0).torus_delta(&at(4, 0), 8, 8), at(4, 0));
+        assert_eq!(at(4, 0).torus_delta(&at(0, 0), 8, 8), at(4, 0));
+        assert_eq!(at(0, 0).torus_delta(&at(0, 3), 8, 7), at(0, 3));
+        assert_eq!(at(0, 0).torus_delta(&at(0, 4), 8, 7), at(0, -3));
+    }
+
+    #[test]
+    fn manhattan_length_counts_moves() {
+        assert_eq!(at(0, 0).manhattan_length(), 0);
+        assert_eq!(at(3, -4).manhattan_length(), 7);
+        assert_eq!(at(-2, -2).manhattan_length(), 4);
+    }
+
+    #[test]
+    fn within_radius_is_a_diamond() {
+        let center = at(5, -3);
+        for radius in 0..5 {
+            let positions: Vec<Position> = center.within_radius(radius).collect();
+            let unique: HashSet<Position> = positions.iter().cloned().collect();
+            assert_eq!(positions.len(), 2 * radius * (radius + 1) + 1);
+            assert_eq!(unique.len(), positions.len());
+            assert!(positions.iter().all(|&position| (position - center).manhattan_length() <= radius));
+        }
+        assert_eq!(center.within_radius(1).next(), Some(at(5, -4)));
+    }
+
+    #[test]
+    fn ring_goes_clockwise_from_the_north() {
+        assert_eq!(at(1, 1).ring(0).collect::<Vec<_>>(), vec![at(1, 1)]);
+        assert_eq!(at(0, 0).ring(2).collect::<Vec<_>>(), vec![
+            at(0, -2), at(1, -1), at(2, 0), at(1, 1), at(0, 2), at(-1, 1), at(-2, 0), at(-1, -1)
+        ]);
+        for radius in 1..6 {
+            let ring: HashSet<Position> = at(3, 3).ring(radius).collect();
+            assert_eq!(ring.len(), 4 * radius);
+            assert!(ring.iter().all(|&position| (position - at(3, 3)).manhattan_length() == radius));
+        }
+    }
+
+    #[test]
+    fn operators_do_not_wrap() {
+        assert_eq!(at(1, 2) + at(3, -5), at(4, -3));
+        assert_eq!(at(1, 2) - at(3, -5), at(-2, 7));
+        assert_eq!(-at(1, -2), at(-1, 2));
+        assert_eq!(at(0, 0) + Direction::North, at(0, -1));
+        assert_eq!(at(0, 0) + Direction::East, at(1, 0));
+        assert_eq!(at(0, 0) + Direction::South, at(0, 1));
+        assert_eq!(at(0, 0) + Direction::West, at(-1, 0));
+        assert_eq!(at(7, 7) + Direction::Still, at(7, 7));
+    }
 }
@@ -0,0 +1,144 @@
+use hlt::constants::Constants;
+use hlt::game::Game;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::PlayerId;
+use hlt::position::Position;
+use hlt::ship::Ship;
+use hlt::ShipId;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Where a ship of one player would be inspired, because at least
+/// `inspiration_ship_count` enemy ships are within `inspiration_radius`.
+/// Inspired ships mine with `inspired_extract_ratio` and get a bonus
+/// of `inspired_bonus_multiplier` times the extracted halite.
+pub struct InspirationMap {
+    /// How many enemy ships are within the radius of every cell.
+    enemies_nearby: Vec<usize>,
+    inspired: Vec<bool>,
+    /// What a ship with enough space mines from every cell this turn, bonus included.
+    yields: Vec<usize>,
+}
+
+impl InspirationMap {
+    pub fn new(
+        game_map: &GameMap, constants: &Constants, ships: &HashMap<ShipId, Ship>, player_id: PlayerId
+    ) -> InspirationMap {
+        let size = game_map.width * game_map.height;
+        let mut enemies_nearby = vec![0; size];
+        if constants.inspiration_enabled {
+            for ship in ships.values().filter(|ship| ship.owner != player_id) {
+                for index in game_map.within_radius(&ship.position, constants.inspiration_radius) {
+                    enemies_nearby[index.0] += 1;
+                }
+            }
+        }
+
+        let inspired: Vec<bool> = enemies_nearby.iter()
+            .map(|&count| constants.inspiration_enabled && count >= constants.inspiration_ship_count)
+            .collect();
+        let yields = game_map.indices()
+            .map(|index| {
+                let (_, gained) = constants.mining_yield(game_map.cell(index).halite, inspired[index.0], usize::MAX);
+                gained
+            })
+            .collect();
+
+        InspirationMap { enemies_nearby, inspired, yields }
+    }
+
+    /// As seen by my player.
+    pub fn for_me<R: BufRead>(game: &Game<R>) -> InspirationMap {
+        InspirationMap::new(&game.game_map, &game.constants, &game.ships, game.my_id)
+    }
+
+    pub fn enemies_nearby(&self, index: CellIndex) -> usize {
+        self.enemies_nearby[index.0]
+    }
+
+    pub fn is_inspired(&self, index: CellIndex) -> bool {
+        self.inspired[index.0]
+    }
+
+    pub fn is_inspired_at(&self, game_map: &GameMap, position: &Position) -> bool {
+        self.is_inspired(game_map.index_of(position))
+    }
+
+    /// Indexed by `CellIndex`, e.g. for `Pathfinder::inspired`.
+    pub fn inspired_cells(&self) -> &[bool] {
+        &self.inspired
+    }
+
+    /// The halite a ship with enough space gets by staying on the cell this turn.
+    pub fn mining_yield(&self, index: CellIndex) -> usize {
+        self.yields[index.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ME: PlayerId = PlayerId(0);
+    const ENEMY: PlayerId = PlayerId(1);
+
+    fn inspiration(game_map: &GameMap, constants: &Constants, ships: &[(PlayerId, i32, i32)]) -> InspirationMap {
+        let ships: HashMap<ShipId, Ship> = ships.iter().enumerate()
+            .map(|(id, &(owner, x, y))| (ShipId(id), Ship::new(owner, ShipId(id), Position { x, y }, 0, 1000)))
+            .collect();
+        InspirationMap::new(game_map, constants, &ships, ME)
+    }
+
+    fn inspired(map: &InspirationMap, game_map: &GameMap, x: i32, y: i32) -> bool {
+        map.is_inspired_at(game_map, &Position { x, y })
+    }
+
+    #[test]
+    fn enemies_inspire_up_to_the_radius() {
+        let game_map = GameMap::new(20, 20, &[100; 400]);
+        let constants = Constants::default();
+        let map = inspiration(&game_map, &constants, &[(ENEMY, 5, 5), (ENEMY, 5, 5)]);
+        assert!(inspired(&map, &game_map, 5, 5));
+        assert!(inspired(&map, &game_map, 9, 5));
+        assert!(inspired(&map, &game_map, 7, 3));
+        assert!(!inspired(&map, &game_map, 10, 5));
+        assert!(!inspired(&map, &game_map, 8, 3));
+        assert_eq!(map.enemies_nearby(game_map.index_of(&Position { x: 8, y: 3 })), 0);
+    }
+
+    #[test]
+    fn inspiration_needs_enough_enemy_ships() {
+        let game_map = GameMap::new(20, 20, &[100; 400]);
+        let constants = Constants::default();
+        let center = game_map.index_of(&Position { x: 5, y: 5 });
+
+        let one_enemy = inspiration(&game_map, &constants, &[(ENEMY, 5, 5), (ME, 5, 6), (ME, 6, 5)]);
+        assert_eq!(one_enemy.enemies_nearby(center), 1);
+        assert!(!one_enemy.is_inspired(center));
+        assert_eq!(one_enemy.mining_yield(center), 25);
+
+        let two_enemies = inspiration(&game_map, &constants, &[(ENEMY, 5, 5), (ENEMY, 7, 7)]);
+        assert_eq!(two_enemies.enemies_nearby(center), 2);
+        assert!(two_enemies.is_inspired(center));
+        assert_eq!(two_enemies.mining_yield(center), 75);
+
+        let mut disabled = constants.clone();
+        disabled.inspiration_enabled = false;
+        let without = inspiration(&game_map, &disabled, &[(ENEMY, 5, 5), (ENEMY, 7, 7)]);
+        assert!(!without.is_inspired(center));
+        assert!(without.inspired_cells().iter().all(|&inspired| !inspired));
+    }
+
+    #[test]
+    fn the_radius_wraps_around_the_map() {
+        let game_map = GameMap::new(20, 20, &[100; 400]);
+        let constants = Constants::default();
+        let map = inspiration(&game_map, &constants, &[(ENEMY, 1, 0), (ENEMY, 19, 0)]);
+        assert!(inspired(&map, &game_map, 17, 0));
+        assert!(inspired(&map, &game_map, 0, 18));
+        assert!(inspired(&map, &game_map, 0, 2));
+        assert!(!inspired(&map, &game_map, 5, 0));
+        assert_eq!(map.enemies_nearby(game_map.index_of(&Position { x: 5, y: 0 })), 1);
+    }
+}
@@ -0,0 +1,35 @@
+//! The Halite III API (`hlt`), the bots built on it and the simulator.
+//! `main.rs` only picks a strategy and connects it to the engine.
+
+extern crate rand;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+extern crate zstd;
+
+pub mod hlt;
+pub mod move_random_and_back;
+pub mod move_resolver;
+pub mod ship_bot;
+pub mod extended_map;
+pub mod complex_action;
+pub mod distance_field;
+pub mod halite_clusters;
+pub mod halite_field;
+pub mod inspiration_map;
+pub mod pathfinding;
+pub mod reservation_table;
+pub mod strategy;
+pub mod threat_map;
+pub mod symmetry;
+pub mod fixed_pattern_bot;
+pub mod example_bot;
+pub mod simulator;
+
+/*
+Move (n, s, e w) | Cost: 10% of halite available at
+turn origin cell is deducted from ship’s current halite.
+
+Move (o) | Collect: 25% of halite available in cell,
+rounded up to the nearest whole number.
+*/
\ No newline at end of file
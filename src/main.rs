@@ -1,42 +1,65 @@
-extern crate rand;
-extern crate core;
+extern crate my_bot;
 
-use hlt::game::Game;
+use my_bot::hlt::error::HltError;
+use my_bot::hlt::game::Game;
+use my_bot::strategy;
 use std::env;
+use std::process;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
-// include
-mod hlt;
-mod move_random_and_back;
-mod ship_bot;
-mod extended_map;
-mod complex_action;
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let rng_seed: u64 = if args.len() > 1 {
-        args[1].parse().unwrap()
-    } else {
-        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+/// Usage: `my_bot [rng_seed] [--strategy name]`.
+/// The strategy can also be set with the HALITE_STRATEGY environment variable.
+fn main() -> Result<(), HltError> {
+    let mut rng_seed: Option<u64> = None;
+    let mut strategy_name = env::var("HALITE_STRATEGY").ok();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--strategy" {
+            strategy_name = args.next();
+        } else {
+            rng_seed = Some(arg.parse().unwrap());
+        }
+    }
+    let rng_seed = rng_seed.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+    let strategy_name = strategy_name.unwrap_or_else(|| strategy::STRATEGY_NAMES[0].to_string());
+
+    // Fail before talking to the engine, there is no log file yet.
+    let mut strategy = match strategy::by_name(&strategy_name, rng_seed) {
+        Some(strategy) => strategy,
+        None => {
+            eprintln!("Unknown strategy '{}'. Known are: {}", strategy_name, strategy::STRATEGY_NAMES.join(", "));
+            process::exit(2);
+        }
     };
 
-    let game = Game::new();
+    let mut game = Game::new()?;
+    game.log.borrow_mut().open(game.my_id.0);
     // At this point "game" variable is populated with initial map data.
     // This is a good place to do computationally expensive start-up pre-processing.
     // As soon as you call "ready" function below, the 2 second per turn timer will start.
-    Game::ready("Julius-Beides");
+    strategy.init(&game);
+    Game::ready("Julius-Beides")?;
 
-    game.log.borrow_mut().log(&format!("Successfully created bot! My Player ID is {}. Bot rng seed is {}.", game.my_id.0, rng_seed));
+    game.log.borrow_mut().log(&format!(
+        "Successfully created bot! My Player ID is {}. Bot rng seed is {}. Strategy is {}.",
+        game.my_id.0, rng_seed, strategy_name));
 
-    //fixed_pattern_bot::run(game);
-    move_random_and_back::run(game);
-}
+    let log = game.log.clone();
+    let result = strategy::play(strategy.as_mut(), &mut game);
 
-/*
-Move (n, s, e w) | Cost: 10% of halite available at
-turn origin cell is deducted from ship’s current halite.
-
-Move (o) | Collect: 25% of halite available in cell,
-rounded up to the nearest whole number.
-*/
\ No newline at end of file
+    // The bots only stop when the engine closes the connection or sends garbage.
+    match result {
+        Err(HltError::UnexpectedEof) => {
+            log.borrow_mut().log("Input connection from server closed. Exiting...");
+            log.borrow_mut().flush();
+            Ok(())
+        },
+        Err(error) => {
+            log.borrow_mut().error(&format!("{}", error));
+            Err(error)
+        },
+        Ok(()) => Ok(()),
+    }
+}
@@ -1,64 +1,127 @@
-use hlt::game::Game;
 use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::game::Game;
+use hlt::command_queue::CommandQueue;
 
+use rand::XorShiftRng;
 use ship_bot::ShipBot;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::BufRead;
+use std::rc::Rc;
 use hlt::ShipId;
+use hlt::game_map::CellIndex;
 use extended_map::ExtendedMap;
+use move_resolver::MoveResolver;
+use reservation_table::ReservationTable;
+use strategy;
+use strategy::Strategy;
 
-pub fn run(mut game: Game) {
+pub struct MoveRandomAndBack {
     // There may be stale/destroyed ships in this map.
-    let mut bot_list: HashMap<ShipId, ShipBot> = HashMap::new();
+    bot_list: HashMap<ShipId, ShipBot>,
+    // Shared by all ship bots.
+    rng: Rc<RefCell<XorShiftRng>>,
+    // Kept for the next turn, with the plans of the ships.
+    reservations: Option<ReservationTable>,
+}
 
-    loop {
-        game.update_frame();
-        let mut command_queue = Vec::new();
-        let mut extended_map = ExtendedMap::new(&game.game_map);
+impl MoveRandomAndBack {
+    pub fn new(rng_seed: u64) -> MoveRandomAndBack {
+        MoveRandomAndBack {
+            bot_list: HashMap::new(),
+            rng: Rc::new(RefCell::new(strategy::seeded_rng(rng_seed))),
+            reservations: None,
+        }
+    }
+}
+
+impl<R: BufRead> Strategy<R> for MoveRandomAndBack {
+    fn next_turn(&mut self, game: &Game<R>) -> Vec<Command> {
+        let mut command_queue = CommandQueue::new();
+        let mut extended_map = ExtendedMap::new(game, self.reservations.take());
 
-        maybe_spawn_ship(&mut extended_map, &game, &mut command_queue);
-        process_ship_bots(&mut extended_map, &game, &mut command_queue, &mut bot_list);
+        maybe_spawn_ship(&mut extended_map, game, &mut command_queue);
+        process_ship_bots(&mut extended_map, game, &mut command_queue, &mut self.bot_list, &self.rng);
+        self.reservations = Some(extended_map.reservations);
 
-        Game::end_turn(&command_queue);
+        command_queue.commands().to_vec()
     }
 }
 
-fn maybe_spawn_ship(
+fn maybe_spawn_ship<R: BufRead>(
     extended_map: &mut ExtendedMap,
-    game: &Game,
-    command_queue: &mut Vec<Command>
+    game: &Game<R>,
+    command_queue: &mut CommandQueue
 ) {
     const SPAWN_UNTIL_TURN: usize = 130;
     const MAX_SHIP_COUNT: usize = 15;
 
     let me = &game.players[game.my_id.0];
-    let shipyard_cell = game.game_map.at_entity(&me.shipyard);
-
     if me.ship_ids.len() < MAX_SHIP_COUNT &&
         game.turn_number <= SPAWN_UNTIL_TURN &&
         me.halite >= game.constants.ship_cost &&
-        extended_map.can_move_safely_then_reserve(&shipyard_cell.position)
+        extended_map.can_spawn_safely_then_reserve(&me.shipyard.position)
     {
         command_queue.push(me.shipyard.spawn());
     }
 }
 
-fn process_ship_bots(
+fn process_ship_bots<R: BufRead>(
     extended_map: &mut ExtendedMap,
-    game: &Game,
-    command_queue: &mut Vec<Command>,
-    bot_list: &mut HashMap<ShipId, ShipBot>
+    game: &Game<R>,
+    command_queue: &mut CommandQueue,
+    bot_list: &mut HashMap<ShipId, ShipBot>,
+    rng: &Rc<RefCell<XorShiftRng>>
 ) {
     let me = &game.players[game.my_id.0];
+    let mut resolver = MoveResolver::new(extended_map.game_map);
 
     for ship_id in &me.ship_ids {
         // If no bot was created for this ship, add a new one.
-        let ship_bot = bot_list.entry(ship_id.clone())
-            .or_insert(ShipBot::new(ship_id, game.log.clone()));
+        let ship_bot = bot_list.entry(*ship_id)
+            .or_insert_with(|| ShipBot::new(ship_id, game.log.clone(), rng.clone()));
 
         // Process the ship bots
-        match ship_bot.next_turn(&game, extended_map) {
-            Ok(command) => command_queue.push(command),
-            Err(message) => print!("{}", message)
+        match ship_bot.plan_turn(game, extended_map) {
+            Ok(request) => resolver.request(request),
+            // Stdout is for the engine only.
+            Err(message) => game.log.borrow_mut().log(&message)
         };
     }
+
+    // Decide all moves together, so no ship has to wait for an earlier one.
+    let mut directions = resolver.resolve(|index| extended_map.is_open_index(index));
+    let mut moves: Vec<(ShipId, CellIndex)> = directions.iter()
+        .map(|(ship_id, &direction)| {
+            let index = extended_map.game_map.index_of(&game.ships[ship_id].position);
+            (*ship_id, extended_map.game_map.neighbour(index, direction))
+        })
+        .collect();
+    // A ship whose move still collides stays. If even staying collides,
+    // it gets no command, so the engine leaves it where it is.
+    while let Some(ship_id) = extended_map.reservations.conflict(&moves, 1) {
+        let start = extended_map.game_map.index_of(&game.ships[&ship_id].position);
+        let conflicting = match moves.iter().position(|&(other, _)| other == ship_id) {
+            Some(conflicting) => conflicting,
+            None => break,
+        };
+        if moves[conflicting].1 != start {
+            game.log.borrow_mut().log(&format!("The move of ship {} collides, it stays.", ship_id.0));
+            moves[conflicting].1 = start;
+            directions.insert(ship_id, Direction::Still);
+        } else {
+            game.log.borrow_mut().log(&format!("Ship {} can't even stay, it gets no command.", ship_id.0));
+            moves.remove(conflicting);
+            directions.remove(&ship_id);
+        }
+    }
+    if !extended_map.reservations.reserve_moves(&moves, 1) {
+        game.log.borrow_mut().log("The moves of this turn couldn't be reserved.");
+    }
+    for ship_id in &me.ship_ids {
+        if let (Some(ship_bot), Some(&direction)) = (bot_list.get_mut(ship_id), directions.get(ship_id)) {
+            command_queue.push(ship_bot.finish_turn(direction, game, extended_map));
+        }
+    }
 }
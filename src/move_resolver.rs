@@ -0,0 +1,261 @@
+use hlt::direction::Direction;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::position::Position;
+use hlt::ShipId;
+use std::collections::HashMap;
+
+/// Staying still is always possible, but worse than any wished direction.
+const STILL_FALLBACK_COST: usize = 8;
+
+/// The directions one ship would like to go this turn, the best first.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveRequest {
+    pub ship_id: ShipId,
+    pub position: Position,
+    pub directions: Vec<Direction>,
+}
+
+/// Decides the moves of all my ships together, so that no two of them end
+/// up on the same cell. A ship may follow into a cell that another one leaves,
+/// and two ships may swap. The i-th wish of a ship costs i, staying still when
+/// it didn't wish so costs `STILL_FALLBACK_COST`, and the total cost is minimal.
+pub struct MoveResolver<'map> {
+    game_map: &'map GameMap,
+    requests: Vec<MoveRequest>,
+}
+
+/// A cell a ship could end up on, and what it costs.
+#[derive(Copy, Clone)]
+struct Choice {
+    cell: usize,
+    cost: i64,
+    direction: Direction,
+}
+
+impl<'map> MoveResolver<'map> {
+    pub fn new(game_map: &'map GameMap) -> MoveResolver<'map> {
+        MoveResolver { game_map, requests: Vec::new() }
+    }
+
+    pub fn request(&mut self, request: MoveRequest) {
+        self.requests.push(request);
+    }
+
+    /// A direction for every requested ship. Ships never move onto a cell that isn't
+    /// open, e.g. because of an enemy ship, but may always stay where they are.
+    pub fn resolve<F>(&self, is_open: F) -> HashMap<ShipId, Direction>
+        where F: Fn(CellIndex) -> bool
+    {
+        let (choices, cell_count) = self.choices(&is_open);
+        let ship_count = self.requests.len();
+        let mut ship_of_cell: Vec<Option<usize>> = vec![None; cell_count];
+        let mut choice_of_ship: Vec<Option<Choice>> = vec![None; ship_count];
+
+        // Hungarian style: add one ship after the other, each time along the
+        // cheapest augmenting path. Only the matched choice of a ship has a
+        // way back, so Bellman-Ford handles the negative costs.
+        for ship in 0..ship_count {
+            let mut ship_cost: Vec<Option<i64>> = vec![None; ship_count];
+            let mut cell_cost: Vec<Option<i64>> = vec![None; cell_count];
+            let mut came_from: Vec<Option<(usize, Choice)>> = vec![None; cell_count];
+            ship_cost[ship] = Some(0);
+
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for from in 0..ship_count {
+                    let cost = match ship_cost[from] {
+                        Some(cost) => cost,
+                        None => continue,
+                    };
+                    for choice in &choices[from] {
+                        let matched = choice_of_ship[from].is_some_and(|matched| matched.cell == choice.cell);
+                        let next = cost + choice.cost;
+                        if !matched && cell_cost[choice.cell].is_none_or(|known| next < known) {
+                            cell_cost[choice.cell] = Some(next);
+                            came_from[choice.cell] = Some((from, *choice));
+                            changed = true;
+                        }
+                    }
+                }
+                for cell in 0..cell_count {
+                    if let (Some(cost), Some(other)) = (cell_cost[cell], ship_of_cell[cell]) {
+                        let back = choice_of_ship[other].expect("A matched ship has an choice.");
+                        let next = cost - back.cost;
+                        if ship_cost[other].is_none_or(|known| next < known) {
+                            ship_cost[other] = Some(next);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+
+            // Staying still is always possible, so some free cell is reachable.
+            let mut cell = (0..cell_count)
+                .filter(|&cell| ship_of_cell[cell].is_none())
+                .filter_map(|cell| cell_cost[cell].map(|cost| (cost, cell)))
+                .min()
+                .map(|(_, cell)| cell)
+                .expect("Every ship can stay where it is.");
+            loop {
+                let (from, choice) = came_from[cell].expect("Reached cells have a predecessor.");
+                let previous = choice_of_ship[from].map(|previous| previous.cell);
+                choice_of_ship[from] = Some(choice);
+                ship_of_cell[cell] = Some(from);
+                match previous {
+                    Some(previous) if from != ship => cell = previous,
+                    _ => break,
+                }
+            }
+        }
+
+        self.requests.iter()
+            .zip(choice_of_ship)
+            .map(|(request, choice)| {
+                (request.ship_id, choice.expect("Every ship is matched.").direction)
+            })
+            .collect()
+    }
+
+    /// The possible cells of every ship with their costs,
+    /// and how many different cells there are.
+    fn choices<F>(&self, is_open: &F) -> (Vec<Vec<Choice>>, usize)
+        where F: Fn(CellIndex) -> bool
+    {
+        let mut cells: HashMap<CellIndex, usize> = HashMap::new();
+        let choices = self.requests.iter()
+            .map(|request| {
+                let start = self.game_map.index_of(&request.position);
+                let mut choices: Vec<Choice> = Vec::new();
+                let wishes = request.directions.iter()
+                    .enumerate()
+                    .map(|(rank, &direction)| (direction, rank as i64))
+                    .chain(Some((Direction::Still, STILL_FALLBACK_COST as i64)));
+                for (direction, cost) in wishes {
+                    let target = self.game_map.neighbour(start, direction);
+                    if direction != Direction::Still && !is_open(target) {
+                        continue;
+                    }
+                    let next_cell = cells.len();
+                    let cell = *cells.entry(target).or_insert(next_cell);
+                    // On a tiny map, two directions can lead to the same cell.
+                    if choices.iter().all(|choice| choice.cell != cell) {
+                        choices.push(Choice { cell, cost, direction });
+                    }
+                }
+                choices
+            })
+            .collect();
+        (choices, cells.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(ship_id: usize, x: i32, y: i32, directions: &[Direction]) -> MoveRequest {
+        MoveRequest { ship_id: ShipId(ship_id), position: Position { x, y }, directions: directions.to_vec() }
+    }
+
+    fn resolve(requests: &[MoveRequest], closed: &[Position]) -> HashMap<ShipId, Direction> {
+        let game_map = GameMap::new(6, 6, &[0; 36]);
+        let mut resolver = MoveResolver::new(&game_map);
+        for request in requests {
+            resolver.request(request.clone());
+        }
+        let closed: Vec<CellIndex> = closed.iter().map(|position| game_map.index_of(position)).collect();
+        resolver.resolve(|index| !closed.contains(&index))
+    }
+
+    #[test]
+    fn two_ships_swap() {
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East]),
+            request(1, 2, 1, &[Direction::West]),
+        ], &[]);
+        assert_eq!(moves[&ShipId(0)], Direction::East);
+        assert_eq!(moves[&ShipId(1)], Direction::West);
+    }
+
+    #[test]
+    fn ships_follow_each_other_in_a_chain() {
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East]),
+            request(1, 2, 1, &[Direction::East]),
+            request(2, 3, 1, &[Direction::South]),
+        ], &[]);
+        assert_eq!(moves[&ShipId(0)], Direction::East);
+        assert_eq!(moves[&ShipId(1)], Direction::East);
+        assert_eq!(moves[&ShipId(2)], Direction::South);
+    }
+
+    #[test]
+    fn ships_move_in_a_cycle() {
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East]),
+            request(1, 2, 1, &[Direction::South]),
+            request(2, 2, 2, &[Direction::West]),
+            request(3, 1, 2, &[Direction::North]),
+        ], &[]);
+        assert_eq!(moves[&ShipId(0)], Direction::East);
+        assert_eq!(moves[&ShipId(1)], Direction::South);
+        assert_eq!(moves[&ShipId(2)], Direction::West);
+        assert_eq!(moves[&ShipId(3)], Direction::North);
+    }
+
+    #[test]
+    fn a_ship_that_stays_blocks_the_chain() {
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East]),
+            request(1, 2, 1, &[Direction::East]),
+            request(2, 3, 1, &[Direction::Still]),
+        ], &[]);
+        assert_eq!(moves[&ShipId(0)], Direction::Still);
+        assert_eq!(moves[&ShipId(1)], Direction::Still);
+        assert_eq!(moves[&ShipId(2)], Direction::Still);
+    }
+
+    #[test]
+    fn the_second_wish_is_taken_when_the_first_is_closed() {
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East, Direction::South]),
+        ], &[Position { x: 2, y: 1 }]);
+        assert_eq!(moves[&ShipId(0)], Direction::South);
+
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East]),
+        ], &[Position { x: 2, y: 1 }]);
+        assert_eq!(moves[&ShipId(0)], Direction::Still);
+    }
+
+    #[test]
+    fn the_total_cost_is_minimal() {
+        // Ship 0 could take its first wish, but then ship 1 would have to stay.
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East, Direction::South]),
+            request(1, 3, 1, &[Direction::West]),
+        ], &[]);
+        assert_eq!(moves[&ShipId(0)], Direction::South);
+        assert_eq!(moves[&ShipId(1)], Direction::West);
+    }
+
+    #[test]
+    fn no_two_ships_end_on_the_same_cell() {
+        let moves = resolve(&[
+            request(0, 1, 1, &[Direction::East]),
+            request(1, 3, 1, &[Direction::West]),
+            request(2, 2, 0, &[Direction::South]),
+            request(3, 2, 2, &[Direction::North]),
+        ], &[]);
+        let game_map = GameMap::new(6, 6, &[0; 36]);
+        let mut ends: Vec<Position> = [(0, 1, 1), (1, 3, 1), (2, 2, 0), (3, 2, 2)].iter()
+            .map(|&(id, x, y)| game_map.normalize(&Position { x, y }.directional_offset(moves[&ShipId(id)])))
+            .collect();
+        ends.sort_by_key(|position| (position.x, position.y));
+        ends.dedup();
+        assert_eq!(ends.len(), 4);
+        assert_eq!(moves.values().filter(|&&direction| direction != Direction::Still).count(), 1);
+    }
+}
@@ -0,0 +1,263 @@
+use hlt::constants::Constants;
+use hlt::direction::Direction;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::position::Position;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A way from one cell to another.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Path {
+    /// From the start to the target, both included.
+    pub cells: Vec<CellIndex>,
+    /// One move per turn, `cells.len() - 1` of them.
+    pub directions: Vec<Direction>,
+    /// The halite that moving along the path burns.
+    pub halite_cost: usize,
+}
+
+impl Path {
+    pub fn turns(&self) -> usize {
+        self.directions.len()
+    }
+}
+
+/// Finds the paths that burn the least halite. Leaving a cell costs
+/// `halite / move_cost_ratio` of that cell, like in the engine.
+pub struct Pathfinder<'map> {
+    game_map: &'map GameMap,
+    move_cost_ratio: usize,
+    inspired_move_cost_ratio: usize,
+    /// How much halite one turn is worth. With 0, the path with the
+    /// least burned halite wins and only then the faster one.
+    pub turn_cost: usize,
+    /// The cells where a ship would be inspired, indexed by `CellIndex`.
+    /// Leaving those costs `halite / inspired_move_cost_ratio`.
+    pub inspired: Option<&'map [bool]>,
+    /// Cells the path must not go through. The start and target may be obstacles.
+    pub obstacles: Option<&'map [bool]>,
+}
+
+impl<'map> Pathfinder<'map> {
+    pub fn new(game_map: &'map GameMap, constants: &Constants) -> Pathfinder<'map> {
+        let inspired_move_cost_ratio = if constants.inspiration_enabled {
+            constants.inspired_move_cost_ratio
+        } else {
+            constants.move_cost_ratio
+        };
+        Pathfinder {
+            game_map,
+            move_cost_ratio: constants.move_cost_ratio,
+            inspired_move_cost_ratio,
+            turn_cost: 0,
+            inspired: None,
+            obstacles: None,
+        }
+    }
+
+    /// The halite it costs to move away from the cell.
+    pub fn move_cost(&self, index: CellIndex) -> usize {
+        let inspired = self.inspired.is_some_and(|inspired| inspired[index.0]);
+        let ratio = if inspired { self.inspired_move_cost_ratio } else { self.move_cost_ratio };
+        self.game_map.cell(index).halite / ratio
+    }
+
+    /// A* search, the remaining distance times `turn_cost` is the estimate.
+    /// None if obstacles block every way.
+    pub fn find_path(&self, start: &Position, target: &Position) -> Option<Path> {
+        let game_map = self.game_map;
+        let start = game_map.index_of(start);
+        let target = game_map.index_of(target);
+        let target_position = game_map.position_of(target);
+        let size = game_map.width * game_map.height;
+
+        // Costs are compared as (weighted cost, turns), so ties go to the faster path.
+        let estimate = |index: CellIndex| {
+            let distance = game_map.calculate_distance(&game_map.position_of(index), &target_position);
+            (distance * self.turn_cost, distance)
+        };
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; size];
+        let mut came_from: Vec<Option<(CellIndex, Direction)>> = vec![None; size];
+        let mut open = BinaryHeap::new();
+
+        best[start.0] = Some((0, 0));
+        let (cost_estimate, turns_estimate) = estimate(start);
+        open.push(Reverse((cost_estimate, turns_estimate, start.0)));
+
+        while let Some(Reverse((_, _, index))) = open.pop() {
+            let index = CellIndex(index);
+            if index == target {
+                return Some(self.build_path(start, target, &came_from));
+            }
+            let (cost, turns) = best[index.0].expect("Only reached cells are queued.");
+            let next = (cost + self.move_cost(index) + self.turn_cost, turns + 1);
+
+            for &direction in &[Direction::North, Direction::East, Direction::South, Direction::West] {
+                let neighbour = game_map.neighbour(index, direction);
+                let blocked = self.obstacles.is_some_and(|obstacles| obstacles[neighbour.0]);
+                if blocked && neighbour != target {
+                    continue;
+                }
+                if best[neighbour.0].is_none_or(|known| next < known) {
+                    best[neighbour.0] = Some(next);
+                    came_from[neighbour.0] = Some((index, direction));
+                    let (cost_estimate, turns_estimate) = estimate(neighbour);
+                    open.push(Reverse((next.0 + cost_estimate, next.1 + turns_estimate, neighbour.0)));
+                }
+            }
+        }
+        None
+    }
+
+    fn build_path(&self, start: CellIndex, target: CellIndex, came_from: &[Option<(CellIndex, Direction)>]) -> Path {
+        let mut cells = vec![target];
+        let mut directions = Vec::new();
+        let mut current = target;
+        while current != start {
+            let (previous, direction) = came_from[current.0].expect("Every reached cell has a predecessor.");
+            cells.push(previous);
+            directions.push(direction);
+            current = previous;
+        }
+        cells.reverse();
+        directions.reverse();
+
+        let halite_cost = cells[..cells.len() - 1].iter()
+            .map(|&index| self.move_cost(index))
+            .sum();
+        Path { cells, directions, halite_cost }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use strategy::seeded_rng;
+
+    /// The halite of the map is given row by row.
+    fn map(width: usize, rows: &[&[usize]]) -> GameMap {
+        let halite: Vec<usize> = rows.iter().flat_map(|row| row.iter().cloned()).collect();
+        GameMap::new(width, rows.len(), &halite)
+    }
+
+    fn position(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+
+    /// The cheapest halite cost with plain Dijkstra, to check the A* against.
+    fn cheapest_cost(pathfinder: &Pathfinder, game_map: &GameMap, start: CellIndex, target: CellIndex) -> usize {
+        let mut best = vec![usize::MAX; game_map.width * game_map.height];
+        let mut open = BinaryHeap::new();
+        best[start.0] = 0;
+        open.push(Reverse((0, start.0)));
+        while let Some(Reverse((cost, index))) = open.pop() {
+            let index = CellIndex(index);
+            if cost > best[index.0] {
+                continue;
+            }
+            for &neighbour in &game_map.neighbours(index) {
+                let next = cost + pathfinder.move_cost(index);
+                if next < best[neighbour.0] {
+                    best[neighbour.0] = next;
+                    open.push(Reverse((next, neighbour.0)));
+                }
+            }
+        }
+        best[target.0]
+    }
+
+    #[test]
+    fn empty_map_takes_the_shortest_way_over_the_edge() {
+        let game_map = GameMap::new(8, 8, &[0; 64]);
+        let pathfinder = Pathfinder::new(&game_map, &Constants::default());
+        let path = pathfinder.find_path(&position(1, 1), &position(7, 1)).unwrap();
+        assert_eq!(path.directions, vec![Direction::West, Direction::West]);
+        assert_eq!(path.cells.len(), 3);
+        assert_eq!(path.halite_cost, 0);
+
+        let path = pathfinder.find_path(&position(3, 3), &position(3, 3)).unwrap();
+        assert_eq!(path.cells, vec![game_map.index_of(&position(3, 3))]);
+        assert_eq!(path.turns(), 0);
+    }
+
+    #[test]
+    fn rich_cells_are_avoided_unless_turns_cost_more() {
+        let game_map = map(9, &[
+            &[0, 0, 0, 0, 0, 0, 0, 0, 0],
+            &[0, 0, 0, 900, 0, 0, 0, 0, 0],
+            &[0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ]);
+        let mut pathfinder = Pathfinder::new(&game_map, &Constants::default());
+        // Straight through burns 90 in 2 turns, around burns nothing in 4 turns.
+        let path = pathfinder.find_path(&position(2, 1), &position(4, 1)).unwrap();
+        assert_eq!((path.halite_cost, path.turns()), (0, 4));
+
+        pathfinder.turn_cost = 20;
+        let path = pathfinder.find_path(&position(2, 1), &position(4, 1)).unwrap();
+        assert_eq!((path.halite_cost, path.turns()), (0, 4));
+
+        pathfinder.turn_cost = 100;
+        let path = pathfinder.find_path(&position(2, 1), &position(4, 1)).unwrap();
+        assert_eq!((path.halite_cost, path.turns()), (90, 2));
+        assert_eq!(path.directions, vec![Direction::East, Direction::East]);
+    }
+
+    #[test]
+    fn inspired_cells_are_cheaper_to_leave() {
+        let game_map = map(3, &[&[300, 300, 0]]);
+        let constants = Constants { inspired_move_cost_ratio: 30, ..Constants::default() };
+        let mut pathfinder = Pathfinder::new(&game_map, &constants);
+        let start = game_map.index_of(&position(0, 0));
+        assert_eq!(pathfinder.move_cost(start), 30);
+
+        let inspired = [true, false, false];
+        pathfinder.inspired = Some(&inspired);
+        assert_eq!(pathfinder.move_cost(start), 10);
+        let path = pathfinder.find_path(&position(0, 0), &position(1, 0)).unwrap();
+        assert_eq!(path.halite_cost, 10);
+    }
+
+    #[test]
+    fn obstacles_block_the_way_but_not_the_target() {
+        let game_map = GameMap::new(5, 1, &[0; 5]);
+        let pathfinder_with = |obstacles| {
+            let mut pathfinder = Pathfinder::new(&game_map, &Constants::default());
+            pathfinder.obstacles = Some(obstacles);
+            pathfinder
+        };
+        let obstacles = [false, true, false, false, false];
+        let path = pathfinder_with(&obstacles).find_path(&position(0, 0), &position(2, 0)).unwrap();
+        assert_eq!(path.directions, vec![Direction::West, Direction::West, Direction::West]);
+        let path = pathfinder_with(&obstacles).find_path(&position(0, 0), &position(1, 0)).unwrap();
+        assert_eq!(path.directions, vec![Direction::East]);
+
+        let walled_in = [false, true, false, false, true];
+        assert_eq!(pathfinder_with(&walled_in).find_path(&position(0, 0), &position(2, 0)), None);
+    }
+
+    #[test]
+    fn finds_the_cheapest_path_on_random_maps() {
+        let mut rng = seeded_rng(13);
+        for _ in 0..20 {
+            let (width, height) = (rng.gen_range(2, 9), rng.gen_range(2, 9));
+            let halite: Vec<usize> = (0..width * height).map(|_| rng.gen_range(0, 1000)).collect();
+            let game_map = GameMap::new(width, height, &halite);
+            let pathfinder = Pathfinder::new(&game_map, &Constants::default());
+            for _ in 0..10 {
+                let start = CellIndex(rng.gen_range(0, width * height));
+                let target = CellIndex(rng.gen_range(0, width * height));
+                let path = pathfinder.find_path(&game_map.position_of(start), &game_map.position_of(target)).unwrap();
+                assert_eq!(path.halite_cost, cheapest_cost(&pathfinder, &game_map, start, target));
+
+                // The cells follow the directions.
+                assert_eq!(path.cells[0], start);
+                assert_eq!(path.cells[path.cells.len() - 1], target);
+                for (step, direction) in path.directions.iter().enumerate() {
+                    assert_eq!(game_map.neighbour(path.cells[step], *direction), path.cells[step + 1]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::ShipId;
+use std::collections::HashMap;
+
+/// Who will be on a cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Occupant {
+    Ship(ShipId),
+    /// The ship that a spawn command creates.
+    NewShip,
+}
+
+/// Which cell every ship will be on in the next few turns, so plans
+/// over several turns don't run into each other. Turn 0 is where the
+/// ships are now, turn 1 is after the commands of this turn.
+///
+/// A ship that hasn't booked a turn yet might stay where it was, so its
+/// cell stays blocked. Once it books another cell, the next ship may follow.
+/// Ships swapping their cells is fine, the engine only checks where they end.
+///
+/// The table is kept from turn to turn with `advance`, so the plans
+/// of the ships still hold when the others plan the next turn.
+pub struct ReservationTable {
+    size: usize,
+    horizon: usize,
+    /// Indexed by `turn * size + cell`.
+    cells: Vec<Option<Occupant>>,
+    /// The booked cell of every ship, indexed by turn.
+    ships: HashMap<ShipId, Vec<Option<CellIndex>>>,
+}
+
+impl ReservationTable {
+    /// Bookings can be made up to `horizon` turns ahead.
+    pub fn new(game_map: &GameMap, horizon: usize) -> ReservationTable {
+        let size = game_map.width * game_map.height;
+        ReservationTable {
+            size,
+            horizon,
+            cells: vec![None; size * (horizon + 1)],
+            ships: HashMap::new(),
+        }
+    }
+
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    /// Where a ship is now.
+    pub fn occupy(&mut self, ship_id: ShipId, index: CellIndex) {
+        self.cells[index.0] = Some(Occupant::Ship(ship_id));
+        self.ship_turns(ship_id)[0] = Some(index);
+    }
+
+    /// Moves on to the next turn, so turn 1 becomes turn 0 and so on. `positions` are
+    /// where the ships are now. A ship that is where it booked to be keeps the rest
+    /// of its bookings, all others lose them. Spawns have to be booked again.
+    pub fn advance(&mut self, positions: &HashMap<ShipId, CellIndex>) {
+        let size = self.size;
+        self.cells.drain(..size);
+        self.cells.extend(vec![None; size]);
+        for cell in &mut self.cells[..size] {
+            *cell = None;
+        }
+        for turns in self.ships.values_mut() {
+            turns.remove(0);
+            turns.push(None);
+        }
+
+        let ship_ids: Vec<ShipId> = self.ships.keys().cloned().collect();
+        for ship_id in ship_ids {
+            if self.ships[&ship_id][0] != positions.get(&ship_id).cloned() {
+                self.release(ship_id);
+                self.ships.remove(&ship_id);
+            }
+        }
+        for (&ship_id, &index) in positions {
+            self.occupy(ship_id, index);
+        }
+    }
+
+    /// None if nobody booked the cell, or the turn is beyond the horizon.
+    pub fn occupant(&self, index: CellIndex, turn: usize) -> Option<Occupant> {
+        if turn > self.horizon {
+            return None;
+        }
+        self.cells[turn * self.size + index.0]
+    }
+
+    /// The cell the ship booked for the turn.
+    pub fn booked_cell(&self, ship_id: ShipId, turn: usize) -> Option<CellIndex> {
+        self.ships.get(&ship_id).and_then(|turns| turns.get(turn).cloned().unwrap_or(None))
+    }
+
+    /// Beyond the horizon, everything is free.
+    pub fn is_free(&self, ship_id: ShipId, index: CellIndex, turn: usize) -> bool {
+        self.is_free_for(Occupant::Ship(ship_id), index, turn)
+    }
+
+    /// Books the cell if it is free. A ship has one cell per turn, so an earlier
+    /// booking for the same turn is dropped, and with it the later ones.
+    pub fn reserve(&mut self, ship_id: ShipId, index: CellIndex, turn: usize) -> bool {
+        if !self.is_free(ship_id, index, turn) {
+            return false;
+        }
+        self.book(ship_id, index, turn);
+        true
+    }
+
+    /// Books without checking, for moves that are known to be safe,
+    /// e.g. from the `MoveResolver`. Booking the cell the ship already has
+    /// keeps its later bookings, another cell drops them.
+    pub fn book(&mut self, ship_id: ShipId, index: CellIndex, turn: usize) {
+        if turn == 0 || turn > self.horizon {
+            return;
+        }
+        match self.booked_cell(ship_id, turn) {
+            Some(previous) if previous == index => return,
+            Some(_) => self.release_from(ship_id, turn),
+            None => (),
+        }
+        self.cells[turn * self.size + index.0] = Some(Occupant::Ship(ship_id));
+        self.ship_turns(ship_id)[turn] = Some(index);
+    }
+
+    /// Books the cells of a path, the first one is where the ship is now.
+    /// All turns up to the horizon or nothing.
+    pub fn reserve_path(&mut self, ship_id: ShipId, cells: &[CellIndex]) -> bool {
+        let free = cells.iter()
+            .enumerate()
+            .skip(1)
+            .all(|(turn, &index)| self.is_free(ship_id, index, turn));
+        if free {
+            for (turn, &index) in cells.iter().enumerate().skip(1) {
+                self.reserve(ship_id, index, turn);
+            }
+        }
+        free
+    }
+
+    /// Books the moves of several ships for the same turn at once. Because all ships move
+    /// together, they may swap cells, follow each other in a chain or move in a cycle.
+    /// All moves or nothing.
+    pub fn reserve_moves(&mut self, moves: &[(ShipId, CellIndex)], turn: usize) -> bool {
+        if turn == 0 || turn > self.horizon {
+            return turn > self.horizon;
+        }
+        let free = self.conflict(moves, turn).is_none();
+        if free {
+            // First make room, so the ships can take each other's cells.
+            for &(ship_id, index) in moves {
+                if self.booked_cell(ship_id, turn).is_some_and(|previous| previous != index) {
+                    self.release_from(ship_id, turn);
+                }
+            }
+            for &(ship_id, index) in moves {
+                self.book(ship_id, index, turn);
+            }
+        }
+        free
+    }
+
+    /// A ship of the batch that can't make its move, or None if all of them can.
+    /// When two ships want the same cell, it is the one that moves there.
+    pub fn conflict(&self, moves: &[(ShipId, CellIndex)], turn: usize) -> Option<ShipId> {
+        if turn == 0 || turn > self.horizon {
+            return None;
+        }
+        let moving: HashMap<ShipId, CellIndex> = moves.iter().cloned().collect();
+        let mut targets: HashMap<CellIndex, ShipId> = HashMap::new();
+        for &(ship_id, index) in moves {
+            if let Some(other) = targets.insert(index, ship_id) {
+                let stays = self.booked_cell(other, turn - 1) == Some(index);
+                return Some(if stays { ship_id } else { other });
+            }
+            let occupant = Occupant::Ship(ship_id);
+            let booked = self.occupant(index, turn)
+                .is_some_and(|other| other != occupant && !self.is_rebooked(other, &moving));
+            // Whoever is there now must go somewhere else, the distinct targets
+            // ensure that a ship of this batch doesn't stay on it.
+            let left_behind = match self.occupant(index, turn - 1) {
+                Some(Occupant::Ship(other)) if other != ship_id =>
+                    !moving.contains_key(&other) && self.booked_cell(other, turn).is_none(),
+                _ => false,
+            };
+            if booked || left_behind {
+                return Some(ship_id);
+            }
+        }
+        None
+    }
+
+    /// Books the shipyard for a ship that is spawned this turn.
+    /// A ship that is on the shipyard now might still have to stay there.
+    pub fn reserve_spawn(&mut self, index: CellIndex) -> bool {
+        if self.occupant(index, 0).is_some() || !self.is_free_for(Occupant::NewShip, index, 1) {
+            return false;
+        }
+        self.cells[self.size + index.0] = Some(Occupant::NewShip);
+        true
+    }
+
+    /// Drops every future booking of the ship, e.g. to plan again. Another ship may
+    /// already follow into the cell it leaves, so it has to book turn 1 again.
+    pub fn release(&mut self, ship_id: ShipId) {
+        self.release_from(ship_id, 1);
+    }
+
+    /// Drops the bookings of the ship from that turn on.
+    fn release_from(&mut self, ship_id: ShipId, first_turn: usize) {
+        if let Some(turns) = self.ships.get_mut(&ship_id) {
+            for (turn, cell) in turns.iter_mut().enumerate().skip(first_turn.max(1)) {
+                if let Some(index) = cell.take() {
+                    let booking = &mut self.cells[turn * self.size + index.0];
+                    if *booking == Some(Occupant::Ship(ship_id)) {
+                        *booking = None;
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_free_for(&self, occupant: Occupant, index: CellIndex, turn: usize) -> bool {
+        if turn == 0 || turn > self.horizon {
+            return turn > self.horizon;
+        }
+        if self.occupant(index, turn).is_some_and(|other| other != occupant) {
+            return false;
+        }
+        // Whoever was there the turn before must have booked another cell.
+        match self.occupant(index, turn - 1) {
+            Some(Occupant::Ship(other)) if Occupant::Ship(other) != occupant =>
+                self.booked_cell(other, turn).is_some(),
+            _ => true,
+        }
+    }
+
+    /// An earlier booking that the batch of moves replaces.
+    fn is_rebooked(&self, occupant: Occupant, moving: &HashMap<ShipId, CellIndex>) -> bool {
+        match occupant {
+            Occupant::Ship(ship_id) => moving.contains_key(&ship_id),
+            Occupant::NewShip => false,
+        }
+    }
+
+    fn ship_turns(&mut self, ship_id: ShipId) -> &mut Vec<Option<CellIndex>> {
+        let horizon = self.horizon;
+        self.ships.entry(ship_id).or_insert_with(|| vec![None; horizon + 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: ShipId = ShipId(1);
+    const B: ShipId = ShipId(2);
+    const C: ShipId = ShipId(3);
+
+    fn table(horizon: usize) -> ReservationTable {
+        ReservationTable::new(&GameMap::new(4, 4, &[0; 16]), horizon)
+    }
+
+    #[test]
+    fn a_booked_cell_is_taken() {
+        let mut table = table(3);
+        assert!(table.reserve(A, CellIndex(5), 1));
+        assert!(!table.reserve(B, CellIndex(5), 1));
+        // A might still stay there afterwards.
+        assert!(!table.reserve(B, CellIndex(5), 2));
+        assert!(table.reserve(A, CellIndex(6), 2));
+        assert!(table.reserve(B, CellIndex(5), 2));
+        assert_eq!(table.occupant(CellIndex(5), 1), Some(Occupant::Ship(A)));
+        assert!(table.is_free(A, CellIndex(5), 1));
+        // Beyond the horizon, nothing is booked.
+        assert!(table.reserve(B, CellIndex(5), 4));
+        assert_eq!(table.occupant(CellIndex(5), 4), None);
+    }
+
+    #[test]
+    fn a_ship_that_did_not_book_blocks_its_cell() {
+        let mut table = table(3);
+        table.occupy(A, CellIndex(0));
+        assert!(!table.reserve(B, CellIndex(0), 1));
+        assert!(table.reserve(A, CellIndex(1), 1));
+        assert!(table.reserve(B, CellIndex(0), 1));
+    }
+
+    #[test]
+    fn rebooking_drops_the_later_turns() {
+        let mut table = table(3);
+        table.occupy(A, CellIndex(0));
+        let path = [CellIndex(0), CellIndex(1), CellIndex(2), CellIndex(3)];
+        assert!(table.reserve_path(A, &path));
+        assert_eq!(table.booked_cell(A, 3), Some(CellIndex(3)));
+
+        // The same cell keeps the plan.
+        table.book(A, CellIndex(1), 1);
+        assert_eq!(table.booked_cell(A, 3), Some(CellIndex(3)));
+
+        table.book(A, CellIndex(4), 1);
+        assert_eq!(table.booked_cell(A, 1), Some(CellIndex(4)));
+        for (turn, &index) in path.iter().enumerate().skip(2) {
+            assert_eq!(table.booked_cell(A, turn), None);
+            assert_eq!(table.occupant(index, turn), None);
+        }
+    }
+
+    #[test]
+    fn release_keeps_the_other_ships() {
+        let mut table = table(2);
+        table.occupy(A, CellIndex(0));
+        table.occupy(B, CellIndex(2));
+        assert!(table.reserve(A, CellIndex(1), 1));
+        assert!(table.reserve(B, CellIndex(0), 1));
+        table.release(A);
+        assert_eq!(table.occupant(CellIndex(1), 1), None);
+        assert_eq!(table.occupant(CellIndex(0), 1), Some(Occupant::Ship(B)));
+        assert_eq!(table.occupant(CellIndex(0), 0), Some(Occupant::Ship(A)));
+    }
+
+    #[test]
+    fn ships_may_swap_follow_and_circle() {
+        let mut table = table(2);
+        table.occupy(A, CellIndex(0));
+        table.occupy(B, CellIndex(1));
+        table.occupy(C, CellIndex(2));
+        // One at a time, nobody can go first.
+        assert!(!table.reserve(A, CellIndex(1), 1));
+
+        assert!(table.reserve_moves(&[(A, CellIndex(1)), (B, CellIndex(0))], 1));
+        assert!(table.reserve_moves(&[(A, CellIndex(1)), (B, CellIndex(2)), (C, CellIndex(3))], 1));
+        assert_eq!(table.occupant(CellIndex(0), 1), None);
+        assert!(table.reserve_moves(&[(A, CellIndex(1)), (B, CellIndex(2)), (C, CellIndex(0))], 1));
+        assert_eq!(table.occupant(CellIndex(0), 1), Some(Occupant::Ship(C)));
+        assert_eq!(table.occupant(CellIndex(3), 1), None);
+    }
+
+    #[test]
+    fn moves_into_a_blocked_cell_book_nothing() {
+        let mut table = table(2);
+        table.occupy(A, CellIndex(0));
+        table.occupy(B, CellIndex(1));
+        table.occupy(C, CellIndex(5));
+        // C stays, so A can't follow.
+        assert!(!table.reserve_moves(&[(A, CellIndex(5)), (B, CellIndex(0))], 1));
+        assert_eq!(table.booked_cell(B, 1), None);
+        // Two ships on one cell.
+        assert!(!table.reserve_moves(&[(A, CellIndex(4)), (B, CellIndex(4))], 1));
+        assert!(table.reserve_spawn(CellIndex(8)));
+        assert!(!table.reserve_moves(&[(A, CellIndex(8))], 1));
+        assert_eq!(table.booked_cell(A, 1), None);
+    }
+
+    #[test]
+    fn the_conflict_is_the_ship_that_moves() {
+        let mut table = table(2);
+        table.occupy(A, CellIndex(0));
+        table.occupy(B, CellIndex(1));
+        table.occupy(C, CellIndex(5));
+        assert_eq!(table.conflict(&[(A, CellIndex(1)), (B, CellIndex(1))], 1), Some(A));
+        assert_eq!(table.conflict(&[(B, CellIndex(1)), (A, CellIndex(1))], 1), Some(A));
+        assert_eq!(table.conflict(&[(A, CellIndex(5)), (B, CellIndex(0))], 1), Some(A));
+        assert_eq!(table.conflict(&[(A, CellIndex(1)), (B, CellIndex(0))], 1), None);
+    }
+
+    #[test]
+    fn no_spawn_while_a_ship_is_on_the_shipyard() {
+        let mut table = table(2);
+        table.occupy(A, CellIndex(0));
+        assert!(table.reserve(A, CellIndex(1), 1));
+        assert!(!table.reserve_spawn(CellIndex(0)));
+        assert!(table.reserve_spawn(CellIndex(2)));
+        assert!(!table.reserve(B, CellIndex(2), 1));
+    }
+
+    #[test]
+    fn advance_keeps_the_plans_that_came_true() {
+        let mut table = table(3);
+        table.occupy(A, CellIndex(0));
+        table.occupy(B, CellIndex(4));
+        table.occupy(C, CellIndex(8));
+        assert!(table.reserve_path(A, &[CellIndex(0), CellIndex(1), CellIndex(2), CellIndex(3)]));
+        assert!(table.reserve_path(B, &[CellIndex(4), CellIndex(5), CellIndex(6)]));
+        assert!(table.reserve_spawn(CellIndex(12)));
+
+        // A moved as planned, B was pushed aside and C was destroyed.
+        let positions: HashMap<ShipId, CellIndex> =
+            vec![(A, CellIndex(1)), (B, CellIndex(9))].into_iter().collect();
+        table.advance(&positions);
+
+        assert_eq!(table.booked_cell(A, 0), Some(CellIndex(1)));
+        assert_eq!(table.booked_cell(A, 1), Some(CellIndex(2)));
+        assert_eq!(table.booked_cell(A, 2), Some(CellIndex(3)));
+        assert_eq!(table.booked_cell(A, 3), None);
+        assert_eq!(table.booked_cell(B, 0), Some(CellIndex(9)));
+        assert_eq!(table.booked_cell(B, 1), None);
+        assert_eq!(table.occupant(CellIndex(6), 1), None);
+        assert_eq!(table.booked_cell(C, 0), None);
+        assert_eq!(table.occupant(CellIndex(8), 0), None);
+        assert_eq!(table.occupant(CellIndex(12), 0), None);
+        assert_eq!(table.occupant(CellIndex(1), 0), Some(Occupant::Ship(A)));
+        assert_eq!(table.occupant(CellIndex(2), 1), Some(Occupant::Ship(A)));
+    }
+}
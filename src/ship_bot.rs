@@ -1,5 +1,3 @@
-extern crate rand;
-
 use std::cell::RefCell;
 use std::rc::Rc;
 use hlt::ship::Ship;
@@ -8,19 +6,40 @@ use hlt::log::Log;
 use hlt::ShipId;
 use hlt::direction::Direction;
 use rand::Rng;
+use rand::XorShiftRng;
 use hlt::game::Game;
 use extended_map::ExtendedMap;
 use complex_action::ComplexAction;
 use hlt::position::Position;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
 use hlt::map_cell::Structure;
-use core::mem;
+use move_resolver::MoveRequest;
+use pathfinding::Pathfinder;
+use threat_map::Safety;
+use std::io::BufRead;
+
+/// How many turns ahead an attack is planned, and how far away a target may be.
+/// The predicted way of the enemy ship is only a guess that gets worse with every
+/// turn, and a longer chase keeps my ship from mining for longer.
+const ATTACK_TURNS: usize = 5;
 
 /* This is a more intelligent ship.
  * It plans a few turns. */
 pub struct ShipBot {
     pub ship_id: ShipId,
     logger: Rc<RefCell<Log>>,
+    // Shared with the other ship bots, seeded from the command line.
+    rng: Rc<RefCell<XorShiftRng>>,
     movement_blocked: u32,
+    // What the ship wished to do this turn and where it heads,
+    // to rank the other directions for the MoveResolver.
+    wish: Direction,
+    heading: Option<Position>,
+    // The way to the dropoff, booked once the first step is granted.
+    planned_path: Option<Vec<CellIndex>>,
+    // The action an attack interrupted, it goes on once the attack ends.
+    interrupted: Option<ComplexAction>,
 
     // Action for next turn. It may be set anywhere in
     // the logic chain.
@@ -33,26 +52,32 @@ pub struct ShipBot {
  * further down in this file. */
 impl ShipBot {
 
-    pub fn new(ship_id: &ShipId, logger: Rc<RefCell<Log>>) -> ShipBot {
+    pub fn new(ship_id: &ShipId, logger: Rc<RefCell<Log>>, rng: Rc<RefCell<XorShiftRng>>) -> ShipBot {
         ShipBot {
-            ship_id: ship_id.clone(),
+            ship_id: *ship_id,
             logger,
+            rng,
             movement_blocked: 0,
+            wish: Direction::Still,
+            heading: None,
+            planned_path: None,
+            interrupted: None,
             //current_action: ComplexAction::still(),
             next_action: Some(ComplexAction::Undefined),
         }
     }
 
-    /// Processes the AI to come up with a Command.
+    /// Processes the AI to come up with the directions the ship wishes to go.
     /// Returns an Error if the ship doesn't exist anymore.
-    pub fn next_turn(
-        &mut self, game: &Game, ex_map: &mut ExtendedMap
-    ) -> Result<Command, String> {
+    pub fn plan_turn<R: BufRead>(
+        &mut self, game: &Game<R>, ex_map: &ExtendedMap
+    ) -> Result<MoveRequest, String> {
+        self.heading = None;
+        self.planned_path = None;
 
         // The next_action from previous turn
         // becomes the new current_action.
-        let action_option = mem::replace(
-            &mut self.next_action, None);
+        let action_option = self.next_action.take();
         let current_action = match action_option {
             Some(action) => action,
             None => {
@@ -62,12 +87,33 @@ impl ShipBot {
         };
 
         // First, find out if the ship still exists.
-        let hlt_ship: &Ship;
-        match game.ships.get(&self.ship_id) {
-            Some(ship) => hlt_ship = ship,
+        let hlt_ship: &Ship = match game.ships.get(&self.ship_id) {
+            Some(ship) => ship,
             None =>
                 return Result::Err(format!("The ship {} doesn't exist anymore!", &self.ship_id.0))
-        }
+        };
+
+        // An idle or empty ship looks out for a loaded enemy to ram. Once
+        // that doesn't pay off anymore, the interrupted action goes on.
+        let current_action = match current_action {
+            ComplexAction::Attack(target) if self.intercept(target, hlt_ship, ex_map, game).is_none() => {
+                self.logger.borrow_mut().log(&format!("Attack on ship {} is over", target.0)[..]);
+                self.interrupted.take().unwrap_or(ComplexAction::Undefined)
+            },
+            action => action,
+        };
+        let idle = current_action == ComplexAction::Undefined || hlt_ship.halite == 0;
+        let current_action = match current_action {
+            ComplexAction::Attack(_) => current_action,
+            action if idle => match self.find_attack_target(hlt_ship, ex_map, game) {
+                Some(target) => {
+                    self.interrupted = Some(action).filter(|action| *action != ComplexAction::Undefined);
+                    ComplexAction::Attack(target)
+                },
+                None => action,
+            },
+            action => action,
+        };
 
         // Decide based on current action
         let direction = match current_action {
@@ -77,6 +123,15 @@ impl ShipBot {
             ComplexAction::NavigateCollect(destination) => {
                 self.navigate_or_collect(&destination, hlt_ship, ex_map, game)
             }
+            ComplexAction::Dodge(destination, blocked) => {
+                self.dodge(&destination, &blocked, false, hlt_ship, ex_map, game)
+            }
+            ComplexAction::DodgeCollect(destination, blocked) => {
+                self.dodge(&destination, &blocked, true, hlt_ship, ex_map, game)
+            }
+            ComplexAction::Attack(target) => {
+                self.attack(target, hlt_ship, ex_map, game)
+            }
 
             ComplexAction::Undefined => {
                 self.decide_action(hlt_ship, ex_map, game)
@@ -92,17 +147,254 @@ impl ShipBot {
             self.next_action = Some(current_action);
         }
 
-        let command = hlt_ship.move_ship(direction);
-        return Result::Ok(command);
+        self.wish = direction;
+        let fearless = matches!(self.next_action, Some(ComplexAction::Attack(_)));
+        Result::Ok(MoveRequest {
+            ship_id: self.ship_id,
+            position: hlt_ship.position,
+            directions: self.rank_directions(direction, fearless, hlt_ship, ex_map, game),
+        })
+    }
+
+    /// Takes the direction the MoveResolver granted.
+    /// The move for the next turn must already be booked.
+    pub fn finish_turn<R: BufRead>(
+        &mut self, direction: Direction, game: &Game<R>, ex_map: &mut ExtendedMap
+    ) -> Command {
+        let ship = &game.ships[&self.ship_id];
+        // A dodge that found no free cell stays, which is no progress either.
+        let dodging = self.next_action.as_ref().is_some_and(ComplexAction::is_dodge);
+        let held = direction == Direction::Still && (self.wish != Direction::Still || dodging);
+
+        if held {
+            self.logger.borrow_mut().log(&format!("Blocked, wanted {:?}", self.wish)[..]);
+            self.movement_blocked += 1;
+
+            // Dodging didn't help either.
+            if self.movement_blocked >= 7 {
+                self.movement_blocked = 0;
+                self.next_action = Some(ComplexAction::Navigate(
+                    self.random_position_near(ex_map.game_map, &ship.position, 4, 8)));
+            } else if !dodging {
+                let blocked = ex_map.game_map.normalize(&ship.position.directional_offset(self.wish));
+                self.next_action = self.next_action.take().map(|action| action.dodge(blocked));
+            }
+
+        } else if direction == self.wish {
+            if direction != Direction::Still {
+                self.movement_blocked = 0;
+            }
+            if let Some(path) = self.planned_path.take() {
+                ex_map.reservations.reserve_path(self.ship_id, &path);
+            }
+
+        } else if self.wish == Direction::Still {
+            self.logger.borrow_mut().log(&format!("Making way, going {:?}", direction)[..]);
+
+        // Another way towards the destination.
+        } else {
+            self.movement_blocked = 0;
+        }
+
+        ship.move_ship(direction)
+    }
+
+    /// The wish first, then the other direction towards where the ship heads, then staying.
+    /// A ship that can't pay for moving has no choice. A ship that would
+    /// stay on a dropoff makes way for others, so the entry doesn't jam.
+    /// Cells where an enemy could ram the ship at a loss are left out, unless it attacks,
+    /// and cells no enemy can reach come before the others.
+    fn rank_directions<R: BufRead>(
+        &self, wish: Direction, fearless: bool, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
+    ) -> Vec<Direction> {
+        let move_cost = ex_map.game_map.at_entity(ship).halite / game.constants.move_cost_ratio;
+        if move_cost > ship.halite {
+            return vec![Direction::Still];
+        }
+
+        let mut directions = vec![wish];
+        if let Some(heading) = self.heading {
+            for direction in ex_map.game_map.get_unsafe_moves(&ship.position, &heading) {
+                if !directions.contains(&direction) {
+                    directions.push(direction);
+                }
+            }
+        }
+        if !directions.contains(&Direction::Still) {
+            directions.push(Direction::Still);
+        }
+
+        let safety = |direction: Direction| {
+            let index = ex_map.game_map.index_of(&ship.position.directional_offset(direction));
+            ex_map.threats.safety(index, ship.halite)
+        };
+        directions.retain(|&direction| {
+            direction == Direction::Still || (fearless && direction == wish) || safety(direction) != Safety::Never
+        });
+        // If that could happen right here, better leave, to a safe cell if possible.
+        if !fearless && safety(Direction::Still) == Safety::Never {
+            let mut escapes: Vec<Direction> = Direction::get_all_cardinals().into_iter()
+                .filter(|direction| !directions.contains(direction) && safety(*direction) != Safety::Never)
+                .collect();
+            escapes.sort_by_key(|&direction| safety(direction));
+            let still = ShipBot::rank_of_still(&mut directions);
+            directions.splice(still..still, escapes);
+        }
+        // Within the moves before and after staying, a safe cell beats a risky one.
+        let still = ShipBot::rank_of_still(&mut directions);
+        let rank = |direction: &Direction| {
+            if fearless && *direction == wish { Safety::Safe } else { safety(*direction) }
+        };
+        directions[..still].sort_by_key(rank);
+
+        if wish == Direction::Still && ex_map.game_map.at_entity(ship).structure != Structure::None {
+            for direction in Direction::get_all_cardinals() {
+                if !directions.contains(&direction) && safety(direction) != Safety::Never {
+                    directions.push(direction);
+                }
+            }
+            directions[still + 1..].sort_by_key(rank);
+        }
+        directions
+    }
+
+    /// Where staying is ranked. It is always possible, so it goes last if it is missing.
+    fn rank_of_still(directions: &mut Vec<Direction>) -> usize {
+        match directions.iter().position(|&direction| direction == Direction::Still) {
+            Some(still) => still,
+            None => {
+                directions.push(Direction::Still);
+                directions.len() - 1
+            },
+        }
+    }
+
+
+    /// Steps aside to a free cell that is cheap to leave again, so the blocker
+    /// can pass, and goes on around the blocked cell. Continues to the destination
+    /// once the blocked cell is free or behind the ship.
+    fn dodge<R: BufRead>(
+        &mut self, destination: &Position, blocked: &Position, collect: bool,
+        ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
+    ) -> Direction {
+        let game_map = ex_map.game_map;
+        let towards = game_map.get_unsafe_moves(&ship.position, destination);
+        let is_free_cell = |position: &Position| {
+            ex_map.is_open(position) && !game_map.at_position(position).is_occupied()
+        };
+        let target = |direction: Direction| game_map.normalize(&ship.position.directional_offset(direction));
+        // Every way on from there leads through the blocked cell.
+        let dead_end = |position: &Position| {
+            game_map.get_unsafe_moves(position, destination).iter()
+                .all(|&direction| game_map.normalize(&position.directional_offset(direction)) == *blocked)
+        };
+
+        let passed = game_map.calculate_distance(&ship.position, destination)
+            <= game_map.calculate_distance(blocked, destination);
+        if towards.is_empty() || passed || *blocked == *destination || is_free_cell(blocked) {
+            self.next_action = Some(if collect {
+                ComplexAction::NavigateCollect(*destination)
+            } else {
+                ComplexAction::Navigate(*destination)
+            });
+            return if collect {
+                self.navigate_or_collect(destination, ship, ex_map, game)
+            } else {
+                self.move_in_direction(destination, ship, ex_map, game)
+            };
+        }
+
+        // Around the blocked cell, but not back to where the ship got stuck.
+        let around = towards.iter()
+            .cloned()
+            .filter(|&direction| {
+                let position = target(direction);
+                position != *blocked && is_free_cell(&position) && !dead_end(&position)
+            })
+            .min_by_key(|&direction| game_map.at_position(&target(direction)).halite);
+        if let Some(direction) = around {
+            return direction;
+        }
+
+        // Sideways, not back. With two directions towards the destination, both others are fine.
+        let back = if towards.len() == 1 { Some(towards[0].invert_direction()) } else { None };
+        Direction::get_all_cardinals().into_iter()
+            .filter(|direction| !towards.contains(direction) && Some(*direction) != back)
+            .filter(|&direction| is_free_cell(&target(direction)))
+            .min_by_key(|&direction| game_map.at_position(&target(direction)).halite)
+            .unwrap_or(Direction::Still)
     }
 
+    /// In a 2 player game, a light ship rams a loaded enemy nearby if the dropped
+    /// cargo probably ends up with me. With more players, the others would profit.
+    fn find_attack_target<R: BufRead>(
+        &self, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
+    ) -> Option<ShipId> {
+        if game.players.len() != 2 || ship.halite > game.constants.max_halite / 4 {
+            return None;
+        }
+        let target = game.ships.values()
+            .filter(|enemy| enemy.owner != game.my_id
+                && ex_map.game_map.calculate_distance(&ship.position, &enemy.position) <= ATTACK_TURNS)
+            .filter_map(|enemy| self.intercept(enemy.id, ship, ex_map, game).map(|(_, swing)| (swing, enemy.id.0)))
+            .max()
+            .map(|(_, enemy_id)| ShipId(enemy_id));
+        if let Some(enemy_id) = target {
+            self.logger.borrow_mut().log(&format!("Attacking ship {}", enemy_id.0)[..]);
+        }
+        target
+    }
 
-    fn decide_action(
-        &mut self, ship: &Ship, ex_map: &mut ExtendedMap, game: &Game
+    /// Goes to where the enemy ship will be and waits there.
+    /// `plan_turn` ends the attack once that doesn't pay off anymore.
+    fn attack<R: BufRead>(
+        &mut self, target: ShipId, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
+    ) -> Direction {
+        match self.intercept(target, ship, ex_map, game) {
+            Some((position, _)) => {
+                self.heading = Some(position);
+                ex_map.game_map.get_unsafe_moves(&ship.position, &position).first()
+                    .cloned()
+                    .unwrap_or(Direction::Still)
+            },
+            None => Direction::Still,
+        }
+    }
+
+    /// The first cell on the predicted way of the enemy ship that my ship can reach in time,
+    /// and the expected halite swing of ramming it there. None if the swing is too small.
+    /// Only cells the enemy moves into count, because the cell an enemy ship stays on is
+    /// kept free for it. So a ship that stays and mines is no target.
+    fn intercept<R: BufRead>(
+        &self, target: ShipId, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
+    ) -> Option<(Position, i64)> {
+        let enemy = game.ships.get(&target)?;
+        let game_map = ex_map.game_map;
+        let min_swing = game.constants.max_halite as i64 / 2;
+
+        let path = ex_map.threats.predicted_path(game_map, enemy, ATTACK_TURNS);
+        let start = game_map.index_of(&enemy.position);
+        let (_, index) = path.iter()
+            .enumerate()
+            .filter(|&(turn, &index)| index != if turn == 0 { start } else { path[turn - 1] })
+            .map(|(turn, &index)| (turn + 1, index))
+            .find(|&(turns, index)| {
+                game_map.calculate_distance(&ship.position, &game_map.position_of(index)) <= turns
+            })?;
+        let swing = ex_map.threats.swing(index, ship.halite, enemy.halite);
+        if swing >= min_swing {
+            Some((game_map.position_of(index), swing))
+        } else {
+            None
+        }
+    }
+
+    fn decide_action<R: BufRead>(
+        &mut self, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
     ) -> Direction {
         // If ship is at dropoff, navigate somewhere else
         if ex_map.game_map.at_position(&ship.position).structure != Structure::None {
-            return self.navigate_random(ship, ex_map, game)
+            self.navigate_random(ship, ex_map, game)
 
         // If full, go home.
         } else if ShipBot::is_full(ship, 0.80) {
@@ -110,31 +402,40 @@ impl ShipBot {
 
         // Default: navigate_collect to random location.
         } else {
-            return self.navigate_random_collect(ship, ex_map, game)
+            self.navigate_random_collect(ship, ex_map, game)
         }
     }
 
     /// Moves farther away
-    fn navigate_random(
-        &mut self, ship: &Ship, ex_map: &mut ExtendedMap, game: &Game
+    fn navigate_random<R: BufRead>(
+        &mut self, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
     )-> Direction {
         const MAX_STEPS: i32 = 12;
         const MIN_STEPS: i32 = 8;
 
-        let random = ShipBot::random_position_near(
-            &ship.position, MIN_STEPS, MAX_STEPS);
+        let random = self.random_position_near(
+            ex_map.game_map, &ship.position, MIN_STEPS, MAX_STEPS);
         self.next_action = Some(ComplexAction::Navigate(random));
         self.navigate_or_collect(&random, ship, ex_map, game)
     }
 
-    fn navigate_random_collect(
-        &mut self, ship: &Ship, ex_map: &mut ExtendedMap, game: &Game
+    fn navigate_random_collect<R: BufRead>(
+        &mut self, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
     )-> Direction {
         const MAX_STEPS: i32 = 8;
         const MIN_STEPS: i32 = 4;
-
-        let random = ShipBot::random_position_near(
-            &ship.position, MIN_STEPS, MAX_STEPS);
+        const CANDIDATES: usize = 3;
+
+        // Of a few random spots, go where the most halite is around.
+        let mut random = self.random_position_near(
+            ex_map.game_map, &ship.position, MIN_STEPS, MAX_STEPS);
+        for _ in 1..CANDIDATES {
+            let candidate = self.random_position_near(
+                ex_map.game_map, &ship.position, MIN_STEPS, MAX_STEPS);
+            if ex_map.nearby_halite.at_position(&candidate) > ex_map.nearby_halite.at_position(&random) {
+                random = candidate;
+            }
+        }
         self.next_action = Some(ComplexAction::NavigateCollect(random));
         self.move_in_direction(&random, ship, ex_map, game)
     }
@@ -142,44 +443,64 @@ impl ShipBot {
     /// if ship is full, move to base
     /// if cell is empty, move further
     /// else collect.
-    fn navigate_or_collect(
+    fn navigate_or_collect<R: BufRead>(
         &mut self, destination: &Position,
-        ship: &Ship, ex_map: &mut ExtendedMap, game: &Game,
+        ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>,
     )-> Direction {
 
         let cell_almost_empty = ex_map.game_map.at_entity(ship)
             .halite < game.constants.max_halite / 50;
 
         if ShipBot::is_full(ship, 0.95) {
-            return self.navigate_to_dropoff(ship, ex_map, game);
+            self.navigate_to_dropoff(ship, ex_map, game)
         } else if cell_almost_empty {
-            return self.move_in_direction(destination, ship, ex_map, game);
+            self.move_in_direction(destination, ship, ex_map, game)
         } else {
-            return Direction::Still;
-        };
+            Direction::Still
+        }
     }
 
     /// A ship is considered full if it has more than
     /// FULL_RATIO halite stored.
     fn is_full(ship: &Ship, factor: f64) -> bool {
-        return ship.halite as f64 >= factor * ship.max_halite() as f64;
+        ship.halite as f64 >= factor * ship.max_halite() as f64
     }
 
-    /// currently only shipyard.
-    fn navigate_to_dropoff(
-        &mut self, ship: &Ship, ex_map: &mut ExtendedMap, game: &Game
+    /// The nearest shipyard or dropoff.
+    fn navigate_to_dropoff<R: BufRead>(
+        &mut self, ship: &Ship, ex_map: &ExtendedMap, game: &Game<R>
     ) -> Direction {
 
         let me = &game.players[game.my_id.0];
-        let dropoff_pos = game.game_map.at_entity(&me.shipyard).position;
+        let ship_index = ex_map.game_map.index_of(&ship.position);
+        let dropoff_pos = match ex_map.structure_distances.nearest_source(ship_index) {
+            Some(structure) => ex_map.game_map.position_of(structure),
+            None => game.game_map.normalize(&me.shipyard.position),
+        };
 
         self.next_action = Some(ComplexAction::NavigateCollect(dropoff_pos));
-        return self.move_in_direction(&dropoff_pos, ship, ex_map, game)
+
+        // Take the way that burns the least cargo, if it is free.
+        let mut pathfinder = Pathfinder::new(ex_map.game_map, &game.constants);
+        pathfinder.inspired = Some(ex_map.inspiration.inspired_cells());
+        if let Some(path) = pathfinder.find_path(&ship.position, &dropoff_pos) {
+            if let Some(&direction) = path.directions.first() {
+                if pathfinder.move_cost(ship_index) <= ship.halite
+                    && ex_map.is_open(&ship.position.directional_offset(direction))
+                {
+                    // Once the step is granted, the rest of the way is booked too.
+                    self.heading = Some(dropoff_pos);
+                    self.planned_path = Some(path.cells);
+                    return direction;
+                }
+            }
+        }
+        self.move_in_direction(&dropoff_pos, ship, ex_map, game)
     }
 
-    fn move_in_direction(
+    fn move_in_direction<R: BufRead>(
         &mut self, destination: &Position,
-        ship: &Ship, ex_map: &mut ExtendedMap, game: &Game
+        ship: &Ship, ex_map: &ExtendedMap, _game: &Game<R>
     ) -> Direction {
 
         // if arrived, decide what to do next.
@@ -203,7 +524,7 @@ impl ShipBot {
         /*let move_dir = if possible_dir.len() == 0 {
                 Direction::Still
             } else {
-                possible_dir[rand::thread_rng().gen_range(0, possible_dir.len())]
+                possible_dir[self.rng.borrow_mut().gen_range(0, possible_dir.len())]
             };*/
         let move_dir= match possible_dir.len() {
             0 => Direction::Still,
@@ -224,44 +545,313 @@ impl ShipBot {
             }
         };
 
-        if ex_map.can_move_safely_then_reserve(
-            &ship.position.directional_offset(move_dir))
-        {
-            self.movement_blocked = 0;
-            return move_dir;
-
-
-        // TODO Proper dodge
-        } else {
-            self.movement_blocked += 1;
-
-            if self.movement_blocked >= 7 {
-                self.movement_blocked = 0;
-                self.next_action = Some(ComplexAction::Navigate(
-                    ShipBot::random_position_near(&ship.position, 4, 8)));
-            }
-
-            return Direction::Still;
-        }
+        self.heading = Some(*destination);
+        move_dir
     }
 
     /// Position near the ship that is Distance movements away.
     /// Should give a distribution like two dice.
-    fn random_position_near(pos: &Position, min_steps: i32, max_steps: i32) -> Position {
-        Position {
-            x: pos.x + ShipBot::pos_neg_range(min_steps/2, max_steps/2),
-            y: pos.y + ShipBot::pos_neg_range(min_steps/2,max_steps/2),
-        }
+    fn random_position_near(
+        &self, game_map: &GameMap, pos: &Position, min_steps: i32, max_steps: i32
+    ) -> Position {
+        let offset = Position {
+            x: self.pos_neg_range(min_steps/2, max_steps/2),
+            y: self.pos_neg_range(min_steps/2,max_steps/2),
+        };
+        game_map.normalize(&(*pos + offset))
     }
 
     /// generates a number in the specified range,
     /// but it may also be negative.
-    fn pos_neg_range(min: i32, max: i32) -> i32 {
-        let mut rng = rand::thread_rng();
-        return rng.gen_range(min,max)
+    fn pos_neg_range(&self, min: i32, max: i32) -> i32 {
+        let mut rng = self.rng.borrow_mut();
+        rng.gen_range(min,max)
             * if rng.gen_bool(0.5) { 1 } else { -1 } // +1 or -1
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::player::Player;
+    use hlt::PlayerId;
+    use hlt::shipyard::Shipyard;
+    use move_resolver::MoveResolver;
+    use reservation_table::ReservationTable;
+    use std::collections::HashMap;
+    use std::io;
+    use std::io::Empty;
+    use strategy;
+
+    const ME: PlayerId = PlayerId(0);
+
+    fn at(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
 
+    fn player(id: usize, x: i32, y: i32) -> Player {
+        Player {
+            id: PlayerId(id),
+            shipyard: Shipyard { owner: PlayerId(id), position: at(x, y) },
+            halite: 5000,
+            ship_ids: Vec::new(),
+            dropoff_ids: Vec::new(),
+        }
+    }
+
+    /// An empty 16x16 map. My shipyard is at (2, 4), player 1 has one at (13, 4)
+    /// and player 2 at (8, 12). The ships are (owner, x, y, cargo), numbered from 0.
+    fn game(num_players: usize, ships: &[(usize, i32, i32, usize)]) -> Game<Empty> {
+        let players = vec![player(0, 2, 4), player(1, 13, 4), player(2, 8, 12)];
+        let constants = ::simulator::default_constants(16, 16);
+        let max_halite = constants.max_halite;
+        let mut game = Game::from_state(io::empty(), constants, ME,
+            players.into_iter().take(num_players).collect(), GameMap::new(16, 16, &[0; 16 * 16]));
+        for (id, &(owner, x, y, cargo)) in ships.iter().enumerate() {
+            game.ships.insert(ShipId(id), Ship::new(PlayerId(owner), ShipId(id), at(x, y), cargo, max_halite));
+            game.players[owner].ship_ids.push(ShipId(id));
+        }
+        game.mark_entities();
+        game
+    }
+
+    fn bot(game: &Game<Empty>, ship_id: usize, action: ComplexAction) -> ShipBot {
+        let rng = Rc::new(RefCell::new(strategy::seeded_rng(1)));
+        let mut bot = ShipBot::new(&ShipId(ship_id), game.log.clone(), rng);
+        bot.next_action = Some(action);
+        bot
+    }
+
+    /// Plans, resolves and finishes one turn of the bots, like `process_ship_bots`.
+    fn play_turn(bots: &mut [ShipBot], game: &Game<Empty>, ex_map: &mut ExtendedMap) -> HashMap<ShipId, Direction> {
+        let mut resolver = MoveResolver::new(ex_map.game_map);
+        for bot in bots.iter_mut() {
+            resolver.request(bot.plan_turn(game, ex_map).unwrap());
+        }
+        let directions = resolver.resolve(|index| ex_map.is_open_index(index));
+        let moves: Vec<(ShipId, CellIndex)> = bots.iter()
+            .map(|bot| {
+                let index = ex_map.game_map.index_of(&game.ships[&bot.ship_id].position);
+                (bot.ship_id, ex_map.game_map.neighbour(index, directions[&bot.ship_id]))
+            })
+            .collect();
+        assert!(ex_map.reservations.reserve_moves(&moves, 1));
+        for bot in bots.iter_mut() {
+            bot.finish_turn(directions[&bot.ship_id], game, ex_map);
+        }
+        directions
+    }
 
+    /// Plays the turns and moves the ships like the engine would, with nobody else moving.
+    /// The positions of the bots' ships after every turn.
+    fn play(bots: &mut [ShipBot], game: &mut Game<Empty>, turns: usize) -> Vec<Vec<Position>> {
+        let mut reservations: Option<ReservationTable> = None;
+        let mut positions = Vec::new();
+        for _ in 0..turns {
+            let directions = {
+                let mut ex_map = ExtendedMap::new(game, reservations.take());
+                let directions = play_turn(bots, game, &mut ex_map);
+                reservations = Some(ex_map.reservations);
+                directions
+            };
+            for (ship_id, direction) in directions {
+                let ship = game.ships.get_mut(&ship_id).unwrap();
+                ship.position = game.game_map.normalize(&ship.position.directional_offset(direction));
+            }
+            game.mark_entities();
+            positions.push(bots.iter().map(|bot| game.ships[&bot.ship_id].position).collect());
+        }
+        positions
+    }
+
+    #[test]
+    fn of_two_ships_heading_for_the_same_cell_one_stays() {
+        let mut game = game(2, &[(0, 5, 8, 0), (0, 6, 9, 0)]);
+        let mut bots = vec![
+            bot(&game, 0, ComplexAction::Navigate(at(8, 8))),
+            bot(&game, 1, ComplexAction::Navigate(at(6, 5))),
+        ];
+        let positions = play(&mut bots, &mut game, 1);
+
+        let moved: Vec<usize> = (0..2).filter(|&ship| positions[0][ship] == at(6, 8)).collect();
+        assert_eq!(moved.len(), 1);
+        let held = 1 - moved[0];
+        assert_eq!(positions[0][held], [at(5, 8), at(6, 9)][held]);
+        assert!(bots[held].next_action.as_ref().is_some_and(ComplexAction::is_dodge));
+    }
+
+    #[test]
+    fn a_ship_parked_on_the_shipyard_makes_way() {
+        let mut game = game(2, &[(0, 2, 4, 0), (0, 3, 4, 900)]);
+        let mut bots = vec![
+            bot(&game, 0, ComplexAction::Navigate(at(2, 4))),
+            bot(&game, 1, ComplexAction::NavigateCollect(at(2, 4))),
+        ];
+        let positions = play(&mut bots, &mut game, 1);
+        assert_eq!(positions[0][1], at(2, 4));
+        assert_ne!(positions[0][0], at(2, 4));
+    }
+
+    #[test]
+    fn a_blocked_ship_remembers_the_blocked_cell() {
+        let mut game = game(2, &[(0, 5, 8, 0), (1, 6, 8, 0)]);
+        let mut bots = vec![bot(&game, 0, ComplexAction::Navigate(at(9, 8)))];
+        let positions = play(&mut bots, &mut game, 1);
+        assert_eq!(positions[0][0], at(5, 8));
+        assert_eq!(bots[0].next_action, Some(ComplexAction::Dodge(at(9, 8), at(6, 8))));
+        assert_eq!(bots[0].movement_blocked, 1);
+    }
+
+    #[test]
+    fn a_dodge_without_a_free_cell_counts_as_blocked() {
+        let mut game = game(2, &[(0, 5, 8, 0), (1, 6, 8, 0), (1, 5, 7, 0), (1, 5, 9, 0)]);
+        let dodge = ComplexAction::Dodge(at(9, 8), at(6, 8));
+        let mut bots = vec![bot(&game, 0, dodge.clone())];
+        play(&mut bots, &mut game, 1);
+        assert_eq!(bots[0].next_action, Some(dodge.clone()));
+        assert_eq!(bots[0].movement_blocked, 1);
+
+        // After the seventh time, it gives up and heads somewhere else.
+        bots[0].movement_blocked = 6;
+        let positions = play(&mut bots, &mut game, 1);
+        assert_eq!(positions[0][0], at(5, 8));
+        assert_eq!(bots[0].movement_blocked, 0);
+        assert!(matches!(bots[0].next_action, Some(ComplexAction::Navigate(_))));
+    }
+
+    #[test]
+    fn a_dodging_ship_goes_around_and_navigates_on() {
+        let mut game = game(2, &[(0, 5, 8, 0), (1, 6, 8, 0)]);
+        let mut bots = vec![bot(&game, 0, ComplexAction::Navigate(at(9, 8)))];
+        let mut actions = Vec::new();
+        let mut positions = Vec::new();
+        for _ in 0..8 {
+            positions.extend(play(&mut bots, &mut game, 1).into_iter().map(|ships| ships[0]));
+            actions.push(bots[0].next_action.clone().unwrap());
+        }
+        assert!(!positions.contains(&at(6, 8)));
+        assert!(positions.contains(&at(9, 8)));
+        let dodged = actions.iter().position(ComplexAction::is_dodge).unwrap();
+        assert!(actions[dodged..].contains(&ComplexAction::Navigate(at(9, 8))));
+    }
+
+    #[test]
+    fn a_dodge_ends_once_the_blocked_cell_is_free() {
+        let mut game = game(2, &[(0, 5, 8, 0)]);
+        let mut bots = vec![bot(&game, 0, ComplexAction::DodgeCollect(at(9, 8), at(6, 8)))];
+        let positions = play(&mut bots, &mut game, 1);
+        assert_eq!(positions[0][0], at(6, 8));
+        assert_eq!(bots[0].next_action, Some(ComplexAction::NavigateCollect(at(9, 8))));
+        assert_eq!(bots[0].movement_blocked, 0);
+    }
+
+    /// Ranks the directions of ship 0 with the wish and heading, after the enemy ships moved in.
+    fn ranked(
+        game: &Game<Empty>, wish: Direction, heading: Option<Position>, fearless: bool
+    ) -> Vec<Direction> {
+        let ex_map = ExtendedMap::new(game, None);
+        let mut bot = bot(game, 0, ComplexAction::Undefined);
+        bot.heading = heading;
+        bot.rank_directions(wish, fearless, &game.ships[&ShipId(0)], &ex_map, game)
+    }
+
+    #[test]
+    fn safe_cells_come_before_risky_ones_and_never_cells_are_left_out() {
+        // The enemy can reach (8, 6), where its side collects my cargo, and (7, 7) on my side.
+        let game = game(2, &[(0, 7, 6, 500), (1, 8, 7, 0)]);
+        assert_eq!(ranked(&game, Direction::East, Some(at(6, 7)), false),
+            vec![Direction::West, Direction::South, Direction::Still]);
+        assert_eq!(ranked(&game, Direction::South, Some(at(6, 7)), false),
+            vec![Direction::West, Direction::South, Direction::Still]);
+        // An attacking ship goes for it.
+        assert_eq!(ranked(&game, Direction::East, Some(at(6, 7)), true),
+            vec![Direction::East, Direction::West, Direction::South, Direction::Still]);
+    }
+
+    #[test]
+    fn staying_is_always_ranked() {
+        // Staying on (8, 6) could cost the cargo, so the ship rather leaves to a safe cell.
+        let game = game(2, &[(0, 8, 6, 500), (1, 8, 7, 0)]);
+        assert_eq!(ranked(&game, Direction::Still, None, false),
+            vec![Direction::North, Direction::East, Direction::West, Direction::Still]);
+
+        let mut game = self::game(2, &[(0, 5, 5, 0)]);
+        game.game_map.at_position_mut(&at(5, 5)).halite = 100;
+        assert_eq!(ranked(&game, Direction::East, Some(at(9, 5)), false), vec![Direction::Still]);
+
+        let mut directions = vec![Direction::East, Direction::North];
+        assert_eq!(ShipBot::rank_of_still(&mut directions), 2);
+        assert_eq!(directions, vec![Direction::East, Direction::North, Direction::Still]);
+    }
+
+    #[test]
+    fn with_more_players_losing_the_ship_counts() {
+        let two_players = game(2, &[(0, 7, 6, 0), (1, 8, 7, 0)]);
+        assert_eq!(ranked(&two_players, Direction::East, None, false),
+            vec![Direction::East, Direction::Still]);
+        let three_players = game(3, &[(0, 7, 6, 0), (1, 8, 7, 0)]);
+        assert_eq!(ranked(&three_players, Direction::East, None, false), vec![Direction::Still]);
+    }
+
+    /// Ship 0 is mine and empty at (6, 5). The enemy ships are (x, y, cargo), from ship 1 on.
+    fn attack_game(num_players: usize, enemies: &[(i32, i32, usize)]) -> Game<Empty> {
+        let mut ships = vec![(0, 6, 5, 0)];
+        ships.extend(enemies.iter().map(|&(x, y, cargo)| (1, x, y, cargo)));
+        game(num_players, &ships)
+    }
+
+    fn attack_target(game: &Game<Empty>, cargo: usize) -> Option<ShipId> {
+        let ex_map = ExtendedMap::new(game, None);
+        let mut ship = game.ships[&ShipId(0)].clone();
+        ship.halite = cargo;
+        bot(game, 0, ComplexAction::Undefined).find_attack_target(&ship, &ex_map, game)
+    }
+
+    #[test]
+    fn the_target_is_a_loaded_enemy_with_the_biggest_swing() {
+        // Both return east to their shipyard at (13, 4), through cells on my side.
+        let game = attack_game(2, &[(5, 4, 800), (5, 6, 600)]);
+        assert_eq!(attack_target(&game, 0), Some(ShipId(1)));
+        assert_eq!(attack_target(&game, 250), Some(ShipId(1)));
+        // A loaded ship of mine doesn't ram.
+        assert_eq!(attack_target(&game, 300), None);
+        assert_eq!(attack_target(&attack_game(2, &[(5, 6, 600)]), 0), Some(ShipId(1)));
+        // With more players, the others would profit.
+        assert_eq!(attack_target(&attack_game(3, &[(5, 4, 800), (5, 6, 600)]), 0), None);
+        // Too far away.
+        assert_eq!(attack_target(&attack_game(2, &[(12, 10, 800)]), 0), None);
+    }
+
+    #[test]
+    fn only_cells_the_enemy_moves_into_are_intercepted() {
+        let game = attack_game(2, &[(5, 4, 800)]);
+        let ex_map = ExtendedMap::new(&game, None);
+        let bot = bot(&game, 0, ComplexAction::Undefined);
+        let ship = &game.ships[&ShipId(0)];
+        assert_eq!(bot.intercept(ShipId(1), ship, &ex_map, &game), Some((at(6, 4), 1600)));
+
+        // An enemy that stays and mines keeps its cell, however loaded it is.
+        let mining = attack_game(2, &[(6, 4, 400)]);
+        assert_eq!(attack_target(&mining, 0), None);
+        let ex_map = ExtendedMap::new(&mining, None);
+        assert_eq!(bot.intercept(ShipId(1), ship, &ex_map, &mining), None);
+    }
+
+    #[test]
+    fn the_interrupted_action_goes_on_after_the_attack() {
+        let game = attack_game(2, &[(5, 4, 800)]);
+        let collect = ComplexAction::NavigateCollect(at(6, 10));
+        let mut bot = bot(&game, 0, collect.clone());
+        let request = bot.plan_turn(&game, &ExtendedMap::new(&game, None)).unwrap();
+        assert_eq!(request.directions[0], Direction::North);
+        assert_eq!(bot.next_action, Some(ComplexAction::Attack(ShipId(1))));
+        assert_eq!(bot.interrupted, Some(collect.clone()));
+
+        // The enemy got away.
+        let gone = attack_game(2, &[]);
+        let request = bot.plan_turn(&gone, &ExtendedMap::new(&gone, None)).unwrap();
+        assert_eq!(request.directions[0], Direction::South);
+        assert_eq!(bot.next_action, Some(collect));
+        assert_eq!(bot.interrupted, None);
+    }
+}
@@ -0,0 +1,118 @@
+use hlt::constants::Constants;
+use hlt::position::Position;
+use rand::Rng;
+use rand::XorShiftRng;
+
+const OCTAVES: u32 = 4;
+
+/// The halite of every cell (row by row) and the
+/// shipyard position of every player.
+pub struct GeneratedMap {
+    pub halite: Vec<usize>,
+    pub shipyards: Vec<Position>,
+}
+
+/// Generates a symmetric map in the spirit of the official
+/// fractal value noise generator: One tile is generated and
+/// then mirrored, so every player gets the same conditions.
+/// 2 players split the map horizontally, 3 and 4 players in four tiles.
+pub fn generate(
+    rng: &mut XorShiftRng, constants: &Constants, width: usize, height: usize, num_players: usize
+) -> GeneratedMap {
+    let (tiles_x, tiles_y) = match num_players {
+        1 => (1, 1),
+        2 => (2, 1),
+        3 | 4 => (2, 2),
+        _ => panic!("The simulator supports 1 to 4 players, not {}.", num_players),
+    };
+    assert!(width.is_multiple_of(tiles_x) && height.is_multiple_of(tiles_y),
+            "A {}x{} map can't be split into {}x{} tiles.", width, height, tiles_x, tiles_y);
+
+    let tile_width = width / tiles_x;
+    let tile_height = height / tiles_y;
+    let tile = generate_tile(rng, constants, tile_width, tile_height);
+
+    let mut halite = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (tile_x, tile_y) = mirror_into_tile(x, y, tile_width, tile_height);
+            halite.push(tile[tile_y * tile_width + tile_x]);
+        }
+    }
+
+    let mut shipyards = Vec::with_capacity(num_players);
+    for player in 0..num_players {
+        let tile_index_x = player % tiles_x;
+        let tile_index_y = player / tiles_x;
+        let local_x = if tile_index_x.is_multiple_of(2) { tile_width / 2 } else { tile_width - 1 - tile_width / 2 };
+        let local_y = if tile_index_y.is_multiple_of(2) { tile_height / 2 } else { tile_height - 1 - tile_height / 2 };
+        let x = tile_index_x * tile_width + local_x;
+        let y = tile_index_y * tile_height + local_y;
+
+        // Like in the official engine, shipyards start on an empty cell.
+        halite[y * width + x] = 0;
+        shipyards.push(Position { x: x as i32, y: y as i32 });
+    }
+
+    GeneratedMap { halite, shipyards }
+}
+
+/// Maps a cell of the full map to the cell of the generated tile.
+/// Every second tile is mirrored.
+fn mirror_into_tile(x: usize, y: usize, tile_width: usize, tile_height: usize) -> (usize, usize) {
+    let local_x = x % tile_width;
+    let local_y = y % tile_height;
+    let mirrored_x = if (x / tile_width).is_multiple_of(2) { local_x } else { tile_width - 1 - local_x };
+    let mirrored_y = if (y / tile_height).is_multiple_of(2) { local_y } else { tile_height - 1 - local_y };
+    (mirrored_x, mirrored_y)
+}
+
+fn generate_tile(rng: &mut XorShiftRng, constants: &Constants, width: usize, height: usize) -> Vec<usize> {
+    let mut noise = vec![0.0; width * height];
+    let mut amplitude = 1.0;
+    let mut frequency = 2;
+
+    for _ in 0..OCTAVES {
+        let grid: Vec<f64> = (0..(frequency + 1) * (frequency + 1))
+            .map(|_| rng.gen::<f64>())
+            .collect();
+
+        for y in 0..height {
+            for x in 0..width {
+                let sample = interpolate(&grid, frequency,
+                    x as f64 * frequency as f64 / width as f64,
+                    y as f64 * frequency as f64 / height as f64);
+                noise[y * width + x] += amplitude * sample;
+            }
+        }
+
+        amplitude *= constants.persistence;
+        frequency *= 2;
+    }
+
+    let min = noise.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = noise.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let range = if max > min { max - min } else { 1.0 };
+    let max_production = rng.gen_range(constants.min_cell_production, constants.max_cell_production + 1) as f64;
+
+    noise.iter()
+        .map(|value| (((value - min) / range).powf(constants.factor_exp_1) * max_production) as usize)
+        .collect()
+}
+
+/// Smooth bilinear interpolation in a grid of random values.
+fn interpolate(grid: &[f64], frequency: usize, x: f64, y: f64) -> f64 {
+    let x0 = x.floor() as usize;
+    let y0 = y.floor() as usize;
+    let tx = smoothstep(x - x0 as f64);
+    let ty = smoothstep(y - y0 as f64);
+    let at = |gx: usize, gy: usize| grid[gy * (frequency + 1) + gx];
+
+    let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1, y0) * tx;
+    let bottom = at(x0, y0 + 1) * (1.0 - tx) + at(x0 + 1, y0 + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
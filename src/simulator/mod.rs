@@ -0,0 +1,629 @@
+pub mod map_generator;
+pub mod replay;
+pub mod replay_reader;
+
+use hlt::command::Command;
+use hlt::command_queue::CommandQueue;
+use hlt::constants::Constants;
+use hlt::direction::Direction;
+use hlt::dropoff::Dropoff;
+use hlt::DropoffId;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::map_cell::Structure;
+use hlt::player::Player;
+use hlt::PlayerId;
+use hlt::position::Position;
+use hlt::ship::Ship;
+use hlt::ShipId;
+use hlt::shipyard::Shipyard;
+use inspiration_map::InspirationMap;
+use simulator::replay::Replay;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+use std::io::Empty;
+use strategy::seeded_rng;
+
+/// The constants the official engine sends for a map of that size.
+/// Like in the engine, the number of turns grows with the map size.
+pub fn default_constants(width: usize, height: usize) -> Constants {
+    /// The number of turns on the largest maps.
+    const MAX_TURNS: usize = 500;
+
+    let defaults = Constants::default();
+    let size = width.max(height).clamp(defaults.min_turn_threshold, defaults.max_turn_threshold);
+    let max_turns = defaults.min_turns + (MAX_TURNS - defaults.min_turns) * (size - defaults.min_turn_threshold)
+        / (defaults.max_turn_threshold - defaults.min_turn_threshold);
+
+    Constants {
+        max_turns,
+        default_map_width: width,
+        default_map_height: height,
+        ..defaults
+    }
+}
+
+/// Something noteworthy that happened during a turn.
+pub enum Event {
+    Spawn { ship_id: ShipId, owner: PlayerId, position: Position },
+    Construct { dropoff_id: DropoffId, owner: PlayerId, position: Position },
+    /// Ships that collided and sank.
+    Shipwreck { position: Position, ship_ids: Vec<ShipId> },
+}
+
+/// What happened in one call to `Simulator::step`.
+pub struct TurnReport {
+    /// The commands that were carried out, indexed by player id.
+    /// Empty for eliminated players.
+    pub moves: Vec<Vec<Command>>,
+    pub events: Vec<Event>,
+    /// The new halite of every cell that changed.
+    pub changed_cells: Vec<(Position, usize)>,
+    /// Halite unloaded at shipyards and dropoffs, indexed by player id.
+    pub deposited: Vec<usize>,
+}
+
+/// An in-process replacement for the official halite engine.
+/// It owns the same state the bots see in `Game` and applies
+/// the commands of all players, one turn per `step`.
+pub struct Simulator {
+    pub constants: Constants,
+    /// The turn the players have to send commands for next.
+    pub turn_number: usize,
+    pub players: Vec<Player>,
+    pub ships: HashMap<ShipId, Ship>,
+    pub dropoffs: HashMap<DropoffId, Dropoff>,
+    pub game_map: GameMap,
+    /// Why a player was thrown out of the game. None if it is still playing.
+    pub eliminations: Vec<Option<String>>,
+    next_ship_id: usize,
+    next_dropoff_id: usize,
+}
+
+impl Simulator {
+    /// Generates a new symmetric map from the seed and
+    /// places every player's shipyard on it.
+    pub fn new(mut constants: Constants, num_players: usize, width: usize, height: usize, seed: u64) -> Simulator {
+        constants.game_seed = Some(seed);
+        let mut rng = seeded_rng(seed);
+        let generated = map_generator::generate(&mut rng, &constants, width, height, num_players);
+        let mut game_map = GameMap::new(width, height, &generated.halite);
+
+        let mut players = Vec::with_capacity(num_players);
+        for (index, position) in generated.shipyards.into_iter().enumerate() {
+            let id = PlayerId(index);
+            game_map.at_position_mut(&position).structure = Structure::Shipyard(id);
+            players.push(Player {
+                id,
+                shipyard: Shipyard { owner: id, position },
+                halite: constants.initial_halite,
+                ship_ids: Vec::new(),
+                dropoff_ids: Vec::new(),
+            });
+        }
+
+        Simulator {
+            constants,
+            turn_number: 1,
+            players,
+            ships: HashMap::new(),
+            dropoffs: HashMap::new(),
+            game_map,
+            eliminations: vec![None; num_players],
+            next_ship_id: 0,
+            next_dropoff_id: 0,
+        }
+    }
+
+    /// The state the bot of the player sees at the start of the turn,
+    /// as if it was read with `Game::update_frame`.
+    pub fn game_for(&self, player_id: PlayerId) -> Game<Empty> {
+        let mut game = Game::from_state(
+            io::empty(), self.constants.clone(), player_id, self.players.clone(), self.game_map.clone());
+        game.turn_number = self.turn_number;
+        game.ships = self.ships.clone();
+        game.dropoffs = self.dropoffs.clone();
+        game.mark_entities();
+        game
+    }
+
+    pub fn is_eliminated(&self, player_id: PlayerId) -> bool {
+        self.eliminations[player_id.0].is_some()
+    }
+
+    /// The game ends after the last turn, or if at most one player can still act.
+    /// A single player game only ends when that player can't act anymore.
+    pub fn is_finished(&self) -> bool {
+        if self.turn_number > self.constants.max_turns {
+            return true;
+        }
+
+        let active_players = self.players.iter()
+            .filter(|player| !self.is_eliminated(player.id))
+            .filter(|player| !player.ship_ids.is_empty() || player.halite >= self.constants.ship_cost)
+            .count();
+        if self.players.len() > 1 {
+            active_players < 2
+        } else {
+            active_players == 0
+        }
+    }
+
+    /// Player ids ordered from the winner to the loser.
+    /// Eliminated players always rank last.
+    pub fn rankings(&self) -> Vec<PlayerId> {
+        let mut ids: Vec<PlayerId> = self.players.iter().map(|player| player.id).collect();
+        ids.sort_by_key(|id| (self.is_eliminated(*id), usize::MAX - self.players[id.0].halite));
+        ids
+    }
+
+    /// Plays the game to the end. `bot` is asked for the
+    /// commands of every player that is still in the game.
+    /// If a replay is given, every turn is recorded.
+    pub fn play_out<F>(&mut self, mut replay: Option<&mut Replay>, mut bot: F)
+        where F: FnMut(&Simulator, PlayerId) -> Vec<Command>
+    {
+        while !self.is_finished() {
+            let commands: Vec<Vec<Command>> = (0..self.players.len())
+                .map(|index| {
+                    if self.is_eliminated(PlayerId(index)) { Vec::new() } else { bot(self, PlayerId(index)) }
+                })
+                .collect();
+            let report = self.step(&commands);
+            if let Some(replay) = replay.as_mut() {
+                replay.record_turn(self, &report);
+            }
+        }
+    }
+
+    /// Processes one turn. `commands` holds the commands of each player, indexed by player id.
+    /// A player whose commands are invalid is eliminated and loses all ships,
+    /// as the official engine does.
+    pub fn step(&mut self, commands: &[Vec<Command>]) -> TurnReport {
+        assert_eq!(commands.len(), self.players.len());
+        let halite_before = self.all_halite();
+        let mut events = Vec::new();
+
+        // Inspiration depends on the positions at the start of the turn.
+        let inspired = self.inspired_ships();
+
+        // The state is only changed if all commands of a player are valid.
+        let mut moves: Vec<Vec<Command>> = vec![Vec::new(); self.players.len()];
+        let mut orders: Vec<(PlayerId, Command)> = Vec::new();
+        for (index, player_commands) in commands.iter().enumerate() {
+            let player_id = PlayerId(index);
+            if self.is_eliminated(player_id) {
+                continue;
+            }
+            let errors = CommandQueue::check(
+                player_commands, &self.players[index], &self.ships, &self.game_map, &self.constants);
+            match errors.first() {
+                None => {
+                    orders.extend(player_commands.iter().map(|command| (player_id, *command)));
+                    moves[index] = player_commands.clone();
+                },
+                Some(error) => self.eliminate(player_id, error.to_string()),
+            }
+        }
+
+        // Dropoffs are built first, so their credits can pay for spawns.
+        for (player_id, order) in &orders {
+            if let Command::Construct(ship_id) = order {
+                events.push(self.construct_dropoff(*player_id, *ship_id));
+            }
+        }
+
+        let mut moved: HashSet<ShipId> = HashSet::new();
+        for (_, order) in &orders {
+            if let Command::Move(ship_id, direction) = order {
+                if self.move_ship(*ship_id, *direction, inspired.contains(ship_id)) {
+                    moved.insert(*ship_id);
+                }
+            }
+        }
+
+        for (player_id, order) in &orders {
+            if let Command::Spawn = order {
+                let event = self.spawn_ship(*player_id);
+                if let Event::Spawn { ship_id, .. } = event {
+                    moved.insert(ship_id);
+                }
+                events.push(event);
+            }
+        }
+
+        events.extend(self.resolve_collisions());
+        let deposited = self.deposit_cargo();
+
+        // Ships that didn't move collect halite.
+        let mut ship_ids: Vec<ShipId> = self.ships.keys().cloned().collect();
+        ship_ids.sort_by_key(|id| id.0);
+        for ship_id in ship_ids {
+            if !moved.contains(&ship_id) {
+                self.mine(ship_id, inspired.contains(&ship_id));
+            }
+        }
+
+        self.refresh_player_entities();
+        self.turn_number += 1;
+
+        let changed_cells = self.all_halite().into_iter()
+            .zip(halite_before)
+            .filter(|((_, after), (_, before))| after != before)
+            .map(|(cell, _)| cell)
+            .collect();
+        TurnReport { moves, events, changed_cells, deposited }
+    }
+
+    /// The halite of every cell, row by row.
+    fn all_halite(&self) -> Vec<(Position, usize)> {
+        let mut halite = Vec::with_capacity(self.game_map.width * self.game_map.height);
+        for y in 0..self.game_map.height {
+            for x in 0..self.game_map.width {
+                let position = Position { x: x as i32, y: y as i32 };
+                halite.push((position, self.game_map.at_position(&position).halite));
+            }
+        }
+        halite
+    }
+
+    fn eliminate(&mut self, player_id: PlayerId, message: String) {
+        self.ships.retain(|_, ship| ship.owner != player_id);
+        self.players[player_id.0].ship_ids.clear();
+        self.eliminations[player_id.0] = Some(message);
+    }
+
+    /// Ships that have enough enemy ships nearby get the inspired bonus.
+    pub fn inspired_ships(&self) -> HashSet<ShipId> {
+        let mut inspired = HashSet::new();
+        for player in &self.players {
+            let inspiration = InspirationMap::new(&self.game_map, &self.constants, &self.ships, player.id);
+            inspired.extend(player.ship_ids.iter()
+                .filter(|ship_id| inspiration.is_inspired_at(&self.game_map, &self.ships[ship_id].position)));
+        }
+        inspired
+    }
+
+    fn construct_dropoff(&mut self, player_id: PlayerId, ship_id: ShipId) -> Event {
+        let ship = self.ships.remove(&ship_id).expect("validated before");
+        let id = DropoffId(self.next_dropoff_id);
+        self.next_dropoff_id += 1;
+
+        let cell = self.game_map.at_entity_mut(&ship);
+        let credit = ship.halite + cell.halite;
+        cell.halite = 0;
+        cell.structure = Structure::Dropoff(id);
+
+        let player = &mut self.players[player_id.0];
+        player.halite = player.halite + credit - self.constants.dropoff_cost;
+        self.dropoffs.insert(id, Dropoff { owner: player_id, id, position: ship.position });
+        Event::Construct { dropoff_id: id, owner: player_id, position: ship.position }
+    }
+
+    /// Returns false if the ship stays where it is.
+    fn move_ship(&mut self, ship_id: ShipId, direction: Direction, inspired: bool) -> bool {
+        if direction == Direction::Still {
+            return false;
+        }
+
+        let ratio = if inspired {
+            self.constants.inspired_move_cost_ratio
+        } else {
+            self.constants.move_cost_ratio
+        };
+        let ship = self.ships.get_mut(&ship_id).expect("validated before");
+        let cost = self.game_map.at_entity(ship).halite / ratio;
+
+        // Without enough fuel, the ship stays and collects instead.
+        if ship.halite < cost {
+            return false;
+        }
+        ship.halite -= cost;
+        ship.position = self.game_map.normalize(&ship.position.directional_offset(direction));
+        true
+    }
+
+    fn spawn_ship(&mut self, player_id: PlayerId) -> Event {
+        let id = ShipId(self.next_ship_id);
+        self.next_ship_id += 1;
+
+        let player = &mut self.players[player_id.0];
+        player.halite -= self.constants.ship_cost;
+        let ship = Ship::new(player_id, id, player.shipyard.position, 0, self.constants.max_halite);
+        self.ships.insert(id, ship);
+        Event::Spawn { ship_id: id, owner: player_id, position: player.shipyard.position }
+    }
+
+    /// All ships that end up on the same cell sink. Their cargo is dropped into the sea,
+    /// or credited to the owner if they collided on a shipyard or dropoff.
+    fn resolve_collisions(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut by_position: HashMap<Position, Vec<ShipId>> = HashMap::new();
+        for ship in self.ships.values() {
+            by_position.entry(ship.position).or_default().push(ship.id);
+        }
+
+        // Sorted, so the events are always reported in the same order.
+        let mut by_position: Vec<(Position, Vec<ShipId>)> = by_position.into_iter().collect();
+        by_position.sort_by_key(|(position, _)| (position.y, position.x));
+
+        for (position, mut ship_ids) in by_position {
+            if ship_ids.len() < 2 {
+                continue;
+            }
+
+            ship_ids.sort_by_key(|id| id.0);
+            let cargo: usize = ship_ids.iter()
+                .map(|id| self.ships.remove(id).expect("collected above").halite)
+                .sum();
+
+            let owner = match self.game_map.at_position(&position).structure {
+                Structure::Shipyard(player_id) => Some(player_id),
+                Structure::Dropoff(dropoff_id) => Some(self.dropoffs[&dropoff_id].owner),
+                Structure::None => None,
+            };
+            match owner {
+                Some(player_id) => self.players[player_id.0].halite += cargo,
+                None => self.game_map.at_position_mut(&position).halite += cargo,
+            }
+            events.push(Event::Shipwreck { position, ship_ids });
+        }
+        events
+    }
+
+    /// Ships on one of their own structures unload everything.
+    /// Returns how much each player got.
+    fn deposit_cargo(&mut self) -> Vec<usize> {
+        let mut deposited = vec![0; self.players.len()];
+        for ship in self.ships.values_mut() {
+            let owner = match self.game_map.at_entity(ship).structure {
+                Structure::Shipyard(player_id) => Some(player_id),
+                Structure::Dropoff(dropoff_id) => Some(self.dropoffs[&dropoff_id].owner),
+                Structure::None => None,
+            };
+            if owner == Some(ship.owner) {
+                self.players[ship.owner.0].halite += ship.halite;
+                deposited[ship.owner.0] += ship.halite;
+                ship.halite = 0;
+            }
+        }
+        deposited
+    }
+
+    /// Collects a quarter of the cell, rounded up, without overfilling the ship.
+    /// Inspired ships additionally get a bonus that isn't taken from the cell.
+    fn mine(&mut self, ship_id: ShipId, inspired: bool) {
+        let ship = self.ships.get_mut(&ship_id).expect("mined ships exist");
+        let cell = self.game_map.at_entity_mut(ship);
+
+        let space = ship.max_halite() - ship.halite;
+        let (extracted, gained) = self.constants.mining_yield(cell.halite, inspired, space);
+        cell.halite -= extracted;
+        ship.halite += gained;
+    }
+
+    /// Rebuilds the entity lists of every player and
+    /// marks the ship positions on the map.
+    fn refresh_player_entities(&mut self) {
+        let mut ship_ids: Vec<ShipId> = self.ships.keys().cloned().collect();
+        ship_ids.sort_by_key(|id| id.0);
+        let mut dropoff_ids: Vec<DropoffId> = self.dropoffs.keys().cloned().collect();
+        dropoff_ids.sort_by_key(|id| id.0);
+
+        let ships = &self.ships;
+        let dropoffs = &self.dropoffs;
+        for player in &mut self.players {
+            player.ship_ids = ship_ids.iter().cloned()
+                .filter(|id| ships[id].owner == player.id)
+                .collect();
+            player.dropoff_ids = dropoff_ids.iter().cloned()
+                .filter(|id| dropoffs[id].owner == player.id)
+                .collect();
+        }
+
+        self.game_map.clear_ships();
+        for ship in self.ships.values() {
+            self.game_map.at_entity_mut(ship).mark_unsafe(ship.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two players on an empty 16 by 16 map.
+    fn simulator() -> Simulator {
+        let mut simulator = Simulator::new(default_constants(16, 16), 2, 16, 16, 1);
+        for index in simulator.game_map.indices().collect::<Vec<_>>() {
+            simulator.game_map.cell_mut(index).halite = 0;
+        }
+        simulator
+    }
+
+    fn add_ship(simulator: &mut Simulator, owner: usize, x: i32, y: i32, halite: usize) -> ShipId {
+        let id = ShipId(simulator.next_ship_id);
+        simulator.next_ship_id += 1;
+        let max_halite = simulator.constants.max_halite;
+        simulator.ships.insert(id, Ship::new(PlayerId(owner), id, Position { x, y }, halite, max_halite));
+        simulator.refresh_player_entities();
+        id
+    }
+
+    fn set_halite(simulator: &mut Simulator, x: i32, y: i32, halite: usize) {
+        simulator.game_map.at_position_mut(&Position { x, y }).halite = halite;
+    }
+
+    fn halite_at(simulator: &Simulator, x: i32, y: i32) -> usize {
+        simulator.game_map.at_position(&Position { x, y }).halite
+    }
+
+    /// Player 0 sends `commands`, player 1 nothing.
+    fn step(simulator: &mut Simulator, commands: Vec<Command>) -> TurnReport {
+        simulator.step(&[commands, Vec::new()])
+    }
+
+    #[test]
+    fn a_ship_that_stays_mines_a_quarter_rounded_up() {
+        let mut simulator = simulator();
+        let ship = add_ship(&mut simulator, 0, 5, 5, 0);
+        set_halite(&mut simulator, 5, 5, 501);
+        step(&mut simulator, Vec::new());
+        assert_eq!(simulator.ships[&ship].halite, 126);
+        assert_eq!(halite_at(&simulator, 5, 5), 375);
+
+        step(&mut simulator, vec![Command::Move(ship, Direction::Still)]);
+        assert_eq!(simulator.ships[&ship].halite, 126 + 94);
+        assert_eq!(halite_at(&simulator, 5, 5), 281);
+    }
+
+    #[test]
+    fn a_full_ship_mines_only_what_fits() {
+        let mut simulator = simulator();
+        let ship = add_ship(&mut simulator, 0, 5, 5, 990);
+        set_halite(&mut simulator, 5, 5, 800);
+        step(&mut simulator, Vec::new());
+        assert_eq!(simulator.ships[&ship].halite, 1000);
+        assert_eq!(halite_at(&simulator, 5, 5), 790);
+    }
+
+    #[test]
+    fn two_enemies_nearby_inspire_a_ship() {
+        let mut simulator = simulator();
+        let ship = add_ship(&mut simulator, 0, 5, 5, 0);
+        add_ship(&mut simulator, 1, 7, 7, 0);
+        let far = add_ship(&mut simulator, 1, 5, 10, 0);
+        set_halite(&mut simulator, 5, 5, 400);
+        assert!(simulator.inspired_ships().is_empty());
+
+        // Four steps away, right at the inspiration radius.
+        simulator.ships.get_mut(&far).unwrap().position = Position { x: 5, y: 9 };
+        assert_eq!(simulator.inspired_ships(), vec![ship].into_iter().collect());
+        step(&mut simulator, Vec::new());
+        // The bonus is twice the extracted halite, and isn't taken from the cell.
+        assert_eq!(simulator.ships[&ship].halite, 300);
+        assert_eq!(halite_at(&simulator, 5, 5), 300);
+    }
+
+    #[test]
+    fn moving_costs_a_tenth_of_the_cell() {
+        let mut simulator = simulator();
+        let ship = add_ship(&mut simulator, 0, 5, 5, 100);
+        set_halite(&mut simulator, 5, 5, 509);
+        step(&mut simulator, vec![Command::Move(ship, Direction::East)]);
+        assert_eq!(simulator.ships[&ship].position, Position { x: 6, y: 5 });
+        assert_eq!(simulator.ships[&ship].halite, 50);
+        assert_eq!(halite_at(&simulator, 5, 5), 509);
+    }
+
+    #[test]
+    fn a_ship_without_enough_fuel_stays_and_mines() {
+        let mut simulator = simulator();
+        let ship = add_ship(&mut simulator, 0, 5, 5, 49);
+        set_halite(&mut simulator, 5, 5, 500);
+        step(&mut simulator, vec![Command::Move(ship, Direction::East)]);
+        assert_eq!(simulator.ships[&ship].position, Position { x: 5, y: 5 });
+        assert_eq!(simulator.ships[&ship].halite, 49 + 125);
+    }
+
+    #[test]
+    fn ships_that_collide_at_sea_drop_their_cargo() {
+        let mut simulator = simulator();
+        let mine = add_ship(&mut simulator, 0, 5, 5, 300);
+        let theirs = add_ship(&mut simulator, 1, 7, 5, 200);
+        let report = simulator.step(&[
+            vec![Command::Move(mine, Direction::East)],
+            vec![Command::Move(theirs, Direction::West)],
+        ]);
+        assert!(simulator.ships.is_empty());
+        assert!(simulator.players.iter().all(|player| player.ship_ids.is_empty()));
+        assert_eq!(halite_at(&simulator, 6, 5), 500);
+        match &report.events[..] {
+            [Event::Shipwreck { position, ship_ids }] => {
+                assert_eq!(*position, Position { x: 6, y: 5 });
+                assert_eq!(*ship_ids, vec![mine, theirs]);
+            },
+            _ => panic!("Expected one shipwreck."),
+        }
+    }
+
+    #[test]
+    fn ships_that_collide_on_a_shipyard_credit_its_owner() {
+        let mut simulator = simulator();
+        let shipyard = simulator.players[0].shipyard.position;
+        let before = simulator.players[0].halite;
+        let mine = add_ship(&mut simulator, 0, shipyard.x + 1, shipyard.y, 300);
+        let theirs = add_ship(&mut simulator, 1, shipyard.x - 1, shipyard.y, 200);
+        simulator.step(&[
+            vec![Command::Move(mine, Direction::West)],
+            vec![Command::Move(theirs, Direction::East)],
+        ]);
+        assert!(simulator.ships.is_empty());
+        assert_eq!(simulator.players[0].halite, before + 500);
+        assert_eq!(halite_at(&simulator, shipyard.x, shipyard.y), 0);
+    }
+
+    #[test]
+    fn a_ship_on_its_shipyard_unloads() {
+        let mut simulator = simulator();
+        let shipyard = simulator.players[0].shipyard.position;
+        let before = simulator.players[0].halite;
+        let ship = add_ship(&mut simulator, 0, shipyard.x, shipyard.y + 1, 700);
+        let report = step(&mut simulator, vec![Command::Move(ship, Direction::North)]);
+        assert_eq!(simulator.ships[&ship].halite, 0);
+        assert_eq!(simulator.players[0].halite, before + 700);
+        assert_eq!(report.deposited, vec![700, 0]);
+
+        // Not on the shipyard of another player.
+        let enemy_shipyard = simulator.players[1].shipyard.position;
+        let visitor = add_ship(&mut simulator, 0, enemy_shipyard.x, enemy_shipyard.y + 1, 700);
+        step(&mut simulator, vec![Command::Move(visitor, Direction::North)]);
+        assert_eq!(simulator.ships[&visitor].halite, 700);
+    }
+
+    #[test]
+    fn a_dropoff_is_paid_with_the_cargo_and_the_cell() {
+        let mut simulator = simulator();
+        let before = simulator.players[0].halite;
+        let ship = add_ship(&mut simulator, 0, 5, 5, 300);
+        set_halite(&mut simulator, 5, 5, 200);
+        step(&mut simulator, vec![Command::Construct(ship)]);
+        assert!(simulator.ships.is_empty());
+        assert_eq!(simulator.players[0].halite, before + 300 + 200 - 4000);
+        assert_eq!(simulator.players[0].dropoff_ids.len(), 1);
+        assert_eq!(halite_at(&simulator, 5, 5), 0);
+        assert!(matches!(simulator.game_map.at_position(&Position { x: 5, y: 5 }).structure, Structure::Dropoff(_)));
+    }
+
+    #[test]
+    fn a_spawn_collides_with_a_ship_on_the_shipyard() {
+        let mut simulator = simulator();
+        let shipyard = simulator.players[0].shipyard.position;
+        let before = simulator.players[0].halite;
+        let ship = add_ship(&mut simulator, 0, shipyard.x, shipyard.y, 100);
+        step(&mut simulator, vec![Command::Spawn, Command::Move(ship, Direction::Still)]);
+        assert!(simulator.ships.is_empty());
+        // The cargo goes back to the owner of the shipyard.
+        assert_eq!(simulator.players[0].halite, before - 1000 + 100);
+
+        step(&mut simulator, vec![Command::Spawn]);
+        assert_eq!(simulator.players[0].ship_ids.len(), 1);
+    }
+
+    #[test]
+    fn an_invalid_command_eliminates_the_player() {
+        let mut simulator = simulator();
+        let mine = add_ship(&mut simulator, 0, 5, 5, 0);
+        let theirs = add_ship(&mut simulator, 1, 8, 8, 0);
+        simulator.step(&[
+            vec![Command::Move(mine, Direction::East)],
+            vec![Command::Move(mine, Direction::West), Command::Move(theirs, Direction::North)],
+        ]);
+        assert!(simulator.is_eliminated(PlayerId(1)));
+        assert!(!simulator.is_eliminated(PlayerId(0)));
+        assert!(!simulator.ships.contains_key(&theirs));
+        assert_eq!(simulator.ships[&mine].position, Position { x: 6, y: 5 });
+        assert_eq!(simulator.rankings(), vec![PlayerId(0), PlayerId(1)]);
+    }
+}
@@ -0,0 +1,252 @@
+use hlt::command::Command;
+use hlt::position::Position;
+use serde_json;
+use serde_json::Map;
+use serde_json::Value;
+use simulator::Event;
+use simulator::Simulator;
+use simulator::TurnReport;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::mem;
+use std::path::Path;
+use zstd::stream::Encoder;
+
+const ENGINE_VERSION: &str = "1.0.0";
+const REPLAY_FILE_VERSION: usize = 3;
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// Records a simulated game in the replay format of the official engine,
+/// so it can be watched in the visualizer or in Fluorine.
+///
+/// `full_frames[0]` is the state before the first turn. Like in the engine, every
+/// later frame holds the ships at the start of one turn, the moves of that turn,
+/// what happened during it and the halite of the players afterwards.
+pub struct Replay {
+    header: Map<String, Value>,
+    frames: Vec<Value>,
+    /// Total halite each player unloaded so far.
+    deposited: Vec<usize>,
+    /// The last turn each player still had ships or could spawn one.
+    last_turn_alive: Vec<usize>,
+    /// The ships after the last recorded turn, which are the ships at the start of the next one.
+    next_entities: Value,
+}
+
+impl Replay {
+    /// Must be called before the first turn is played.
+    pub fn new(simulator: &Simulator, player_names: &[&str]) -> Replay {
+        assert_eq!(player_names.len(), simulator.players.len());
+
+        let players: Vec<Value> = simulator.players.iter()
+            .zip(player_names)
+            .map(|(player, name)| json!({
+                "energy": player.halite,
+                "entities": [],
+                "factory_location": Replay::location(&player.shipyard.position),
+                "name": name,
+                "player_id": player.id.0,
+            }))
+            .collect();
+
+        let game_map = &simulator.game_map;
+        let grid: Vec<Value> = (0..game_map.height)
+            .map(|y| {
+                let row: Vec<Value> = (0..game_map.width)
+                    .map(|x| json!({ "energy": game_map.at_position(&Position { x: x as i32, y: y as i32 }).halite }))
+                    .collect();
+                Value::Array(row)
+            })
+            .collect();
+
+        let mut header = Map::new();
+        header.insert("ENGINE_VERSION".to_string(), json!(ENGINE_VERSION));
+        header.insert("GAME_CONSTANTS".to_string(), simulator.constants.to_json());
+        header.insert("REPLAY_FILE_VERSION".to_string(), json!(REPLAY_FILE_VERSION));
+        header.insert("map_generator_seed".to_string(), json!(simulator.constants.game_seed.unwrap_or(0)));
+        header.insert("number_of_players".to_string(), json!(simulator.players.len()));
+        header.insert("players".to_string(), Value::Array(players));
+        header.insert("production_map".to_string(), json!({
+            "grid": grid,
+            "height": game_map.height,
+            "map_generator": "fractal value noise",
+            "name": "",
+            "width": game_map.width,
+        }));
+
+        let mut replay = Replay {
+            header,
+            frames: Vec::new(),
+            deposited: vec![0; simulator.players.len()],
+            last_turn_alive: vec![0; simulator.players.len()],
+            next_entities: Replay::entities(simulator),
+        };
+        let entities = replay.next_entities.clone();
+        let initial_frame = replay.frame(simulator, entities, &[], &[], Value::Object(Map::new()));
+        replay.frames.push(initial_frame);
+        replay
+    }
+
+    /// Must be called after every `Simulator::step`.
+    pub fn record_turn(&mut self, simulator: &Simulator, report: &TurnReport) {
+        let turn = simulator.turn_number - 1;
+        for (index, deposited) in report.deposited.iter().enumerate() {
+            self.deposited[index] += deposited;
+            let player = &simulator.players[index];
+            let can_act = !player.ship_ids.is_empty() || player.halite >= simulator.constants.ship_cost;
+            if !simulator.is_eliminated(player.id) && can_act {
+                self.last_turn_alive[index] = turn;
+            }
+        }
+
+        let mut moves = Map::new();
+        for (index, commands) in report.moves.iter().enumerate() {
+            let commands: Vec<Value> = commands.iter().map(Replay::command).collect();
+            moves.insert(index.to_string(), Value::Array(commands));
+        }
+
+        let entities = mem::replace(&mut self.next_entities, Replay::entities(simulator));
+        let frame = self.frame(simulator, entities, &report.events, &report.changed_cells, Value::Object(moves));
+        self.frames.push(frame);
+    }
+
+    /// Writes the replay as JSON, compressed with zstd like the `.hlt` files of the engine.
+    pub fn write<W: Write>(&self, writer: W, compressed: bool) -> io::Result<()> {
+        if compressed {
+            let mut encoder = Encoder::new(writer, COMPRESSION_LEVEL)?;
+            serde_json::to_writer(&mut encoder, &self.to_json())?;
+            encoder.finish()?.flush()
+        } else {
+            let mut writer = writer;
+            serde_json::to_writer(&mut writer, &self.to_json())?;
+            writer.flush()
+        }
+    }
+
+    /// Saves a compressed `.hlt` file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?), true)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut rankings = vec![0; self.deposited.len()];
+        let final_halite: Vec<usize> = self.player_halite();
+        let mut order: Vec<usize> = (0..self.deposited.len()).collect();
+        order.sort_by_key(|&index| (usize::MAX - self.last_turn_alive[index], usize::MAX - final_halite[index]));
+        for (rank, &index) in order.iter().enumerate() {
+            rankings[index] = rank + 1;
+        }
+
+        let player_statistics: Vec<Value> = (0..self.deposited.len())
+            .map(|index| json!({
+                "player_id": index,
+                "rank": rankings[index],
+                "final_production": final_halite[index],
+                "total_production": self.deposited[index],
+                "last_turn_alive": self.last_turn_alive[index],
+            }))
+            .collect();
+
+        let mut replay = self.header.clone();
+        replay.insert("full_frames".to_string(), Value::Array(self.frames.clone()));
+        replay.insert("game_statistics".to_string(), json!({
+            "number_turns": self.frames.len() - 1,
+            "player_statistics": player_statistics,
+        }));
+        Value::Object(replay)
+    }
+
+    /// The halite of every player in the last frame.
+    fn player_halite(&self) -> Vec<usize> {
+        let energy = &self.frames[self.frames.len() - 1]["energy"];
+        (0..self.deposited.len())
+            .map(|index| energy[index.to_string()].as_u64().unwrap_or(0) as usize)
+            .collect()
+    }
+
+    fn frame(
+        &self, simulator: &Simulator, entities: Value, events: &[Event], changed_cells: &[(Position, usize)], moves: Value
+    ) -> Value {
+        let mut energy = Map::new();
+        let mut deposited = Map::new();
+        for player in &simulator.players {
+            let key = player.id.0.to_string();
+            energy.insert(key.clone(), json!(player.halite));
+            deposited.insert(key, json!(self.deposited[player.id.0]));
+        }
+
+        let cells: Vec<Value> = changed_cells.iter()
+            .map(|(position, halite)| json!({ "production": halite, "x": position.x, "y": position.y }))
+            .collect();
+
+        json!({
+            "cells": cells,
+            "deposited": deposited,
+            "energy": energy,
+            "entities": entities,
+            "events": events.iter().map(Replay::event).collect::<Vec<Value>>(),
+            "moves": moves,
+        })
+    }
+
+    /// The ships of every player, with the inspiration they have for the next turn.
+    fn entities(simulator: &Simulator) -> Value {
+        let inspired = simulator.inspired_ships();
+        let mut entities = Map::new();
+        for player in &simulator.players {
+            let mut ships = Map::new();
+            for ship_id in &player.ship_ids {
+                let ship = &simulator.ships[ship_id];
+                ships.insert(ship_id.0.to_string(), json!({
+                    "energy": ship.halite,
+                    "is_inspired": inspired.contains(ship_id),
+                    "x": ship.position.x,
+                    "y": ship.position.y,
+                }));
+            }
+            entities.insert(player.id.0.to_string(), Value::Object(ships));
+        }
+        Value::Object(entities)
+    }
+
+    fn event(event: &Event) -> Value {
+        match event {
+            Event::Spawn { ship_id, owner, position } => json!({
+                "energy": 0,
+                "id": ship_id.0,
+                "location": Replay::location(position),
+                "owner_id": owner.0,
+                "type": "spawn",
+            }),
+            Event::Construct { dropoff_id, owner, position } => json!({
+                "id": dropoff_id.0,
+                "location": Replay::location(position),
+                "owner_id": owner.0,
+                "type": "construct",
+            }),
+            Event::Shipwreck { position, ship_ids } => json!({
+                "location": Replay::location(position),
+                "ships": ship_ids.iter().map(|id| id.0).collect::<Vec<usize>>(),
+                "type": "shipwreck",
+            }),
+        }
+    }
+
+    fn command(command: &Command) -> Value {
+        match command {
+            Command::Spawn => json!({ "type": "g" }),
+            Command::Construct(ship_id) => json!({ "type": "c", "id": ship_id.0 }),
+            Command::Move(ship_id, direction) => json!({
+                "type": "m",
+                "id": ship_id.0,
+                "direction": direction.get_char_encoding().to_string(),
+            }),
+        }
+    }
+
+    fn location(position: &Position) -> Value {
+        json!({ "x": position.x, "y": position.y })
+    }
+}
@@ -0,0 +1,361 @@
+use hlt::command::Command;
+use hlt::constants::Constants;
+use hlt::dropoff::Dropoff;
+use hlt::DropoffId;
+use hlt::error::HltError;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::player::Player;
+use hlt::PlayerId;
+use hlt::position::Position;
+use hlt::ship::Ship;
+use hlt::ShipId;
+use hlt::shipyard::Shipyard;
+use serde_json;
+use serde_json::Map;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Empty;
+use std::path::Path;
+use zstd;
+
+/// The first bytes of every zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// A replay of the official engine (or `Replay`), loaded into memory.
+/// `turns` walks through it and rebuilds the game state of every turn.
+pub struct ReplayReader {
+    pub constants: Constants,
+    pub player_names: Vec<String>,
+    /// The players before the first turn, with their shipyards and initial halite.
+    players: Vec<Player>,
+    initial_map: GameMap,
+    frames: Vec<Value>,
+}
+
+/// The state one bot saw at the start of a turn and the commands every player sent.
+pub struct ReplayTurn {
+    pub game: Game<Empty>,
+    /// The commands of each player, indexed by player id.
+    pub moves: Vec<Vec<Command>>,
+}
+
+impl ReplayReader {
+    /// Loads an `.hlt` file, compressed or not.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ReplayReader, HltError> {
+        ReplayReader::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<ReplayReader, HltError> {
+        let replay: Value = if bytes.starts_with(&ZSTD_MAGIC) {
+            serde_json::from_slice(&zstd::stream::decode_all(bytes)?)
+        } else {
+            serde_json::from_slice(bytes)
+        }.map_err(|error| HltError::InvalidReplay(error.to_string()))?;
+
+        let constants = Constants::new(&field(&replay, "GAME_CONSTANTS")?.to_string())?;
+
+        let mut players = Vec::new();
+        let mut player_names = Vec::new();
+        for (index, player) in array(&replay, "players")?.iter().enumerate() {
+            let id = PlayerId(number(player, "player_id")?);
+            if id.0 != index {
+                return Err(HltError::UnknownPlayer(id.0));
+            }
+            let shipyard = Shipyard { owner: id, position: position(field(player, "factory_location")?)? };
+            let halite = number(player, "energy")?;
+            players.push(Player { id, shipyard, halite, ship_ids: Vec::new(), dropoff_ids: Vec::new() });
+            player_names.push(field(player, "name")?.as_str().unwrap_or_default().to_string());
+        }
+
+        let production_map = field(&replay, "production_map")?;
+        let width = number(production_map, "width")?;
+        let height = number(production_map, "height")?;
+        let mut halite = Vec::with_capacity(width * height);
+        for row in array(production_map, "grid")? {
+            let row = row.as_array().ok_or_else(|| invalid("a row of the grid isn't an array"))?;
+            for cell in row {
+                halite.push(number(cell, "energy")?);
+            }
+        }
+        if halite.len() != width * height {
+            return Err(invalid("the grid doesn't match the size of the map"));
+        }
+
+        let frames = array(&replay, "full_frames")?.clone();
+        if frames.is_empty() {
+            return Err(invalid("there are no frames"));
+        }
+
+        Ok(ReplayReader {
+            constants,
+            player_names,
+            players,
+            initial_map: GameMap::new(width, height, &halite),
+            frames,
+        })
+    }
+
+    pub fn number_of_turns(&self) -> usize {
+        self.frames.len() - 1
+    }
+
+    /// Every turn as seen by the player `my_id`, starting with turn 1.
+    pub fn turns(&self, my_id: PlayerId) -> Result<ReplayTurns<'_>, HltError> {
+        if my_id.0 >= self.players.len() {
+            return Err(HltError::UnknownPlayer(my_id.0));
+        }
+        let mut turns = ReplayTurns {
+            replay: self,
+            my_id,
+            turn_number: 1,
+            game_map: self.initial_map.clone(),
+            halite: self.players.iter().map(|player| player.halite).collect(),
+            dropoffs: HashMap::new(),
+        };
+        turns.apply_frame(&self.frames[0])?;
+        Ok(turns)
+    }
+}
+
+/// Iterates over the turns of a replay. Stops after the first error.
+pub struct ReplayTurns<'a> {
+    replay: &'a ReplayReader,
+    my_id: PlayerId,
+    turn_number: usize,
+    /// The state at the start of `turn_number`.
+    game_map: GameMap,
+    halite: Vec<usize>,
+    dropoffs: HashMap<DropoffId, Dropoff>,
+}
+
+impl<'a> Iterator for ReplayTurns<'a> {
+    type Item = Result<ReplayTurn, HltError>;
+
+    fn next(&mut self) -> Option<Result<ReplayTurn, HltError>> {
+        if self.turn_number >= self.replay.frames.len() {
+            return None;
+        }
+        let turn = self.next_turn();
+        self.turn_number = if turn.is_ok() { self.turn_number + 1 } else { self.replay.frames.len() };
+        Some(turn)
+    }
+}
+
+impl<'a> ReplayTurns<'a> {
+    fn next_turn(&mut self) -> Result<ReplayTurn, HltError> {
+        let replay = self.replay;
+        let frame = &replay.frames[self.turn_number];
+
+        // The entities of a frame are the ships at the start of its turn.
+        let mut players = replay.players.clone();
+        let mut ships = HashMap::new();
+        if let Some(entities) = frame.get("entities").and_then(Value::as_object) {
+            for (owner, player_ships) in entities {
+                let owner = PlayerId(parse_id(owner)?);
+                let player = players.get_mut(owner.0).ok_or(HltError::UnknownPlayer(owner.0))?;
+                for (id, ship) in object(player_ships)? {
+                    let id = ShipId(parse_id(id)?);
+                    let halite = number(ship, "energy")?;
+                    player.ship_ids.push(id);
+                    ships.insert(id, Ship::new(owner, id, position(ship)?, halite, replay.constants.max_halite));
+                }
+                player.ship_ids.sort_by_key(|id| id.0);
+            }
+        }
+
+        let mut dropoffs: Vec<&Dropoff> = self.dropoffs.values().collect();
+        dropoffs.sort_by_key(|dropoff| dropoff.id.0);
+        for dropoff in dropoffs {
+            players[dropoff.owner.0].dropoff_ids.push(dropoff.id);
+        }
+        for player in &mut players {
+            player.halite = self.halite[player.id.0];
+        }
+
+        let mut moves = vec![Vec::new(); players.len()];
+        if let Some(frame_moves) = frame.get("moves").and_then(Value::as_object) {
+            for (player_id, commands) in frame_moves {
+                let player_id = parse_id(player_id)?;
+                let player_moves = moves.get_mut(player_id).ok_or(HltError::UnknownPlayer(player_id))?;
+                let commands = commands.as_array().ok_or_else(|| invalid("moves aren't an array"))?;
+                for command in commands {
+                    player_moves.push(parse_command(command)?);
+                }
+            }
+        }
+
+        let mut game = Game::from_state(
+            io::empty(), replay.constants.clone(), self.my_id, players, self.game_map.clone());
+        game.turn_number = self.turn_number;
+        game.ships = ships;
+        game.dropoffs = self.dropoffs.clone();
+        game.mark_entities();
+
+        self.apply_frame(frame)?;
+        Ok(ReplayTurn { game, moves })
+    }
+
+    /// Applies what happened during the turn of the frame.
+    fn apply_frame(&mut self, frame: &Value) -> Result<(), HltError> {
+        // Like the engine, the next frame only has the cells of this turn as changes.
+        self.game_map.clear_changes();
+        if let Some(cells) = frame.get("cells").and_then(Value::as_array) {
+            for cell in cells {
+                let index = self.game_map.index_of(&position(cell)?);
+                self.game_map.set_halite(index, number(cell, "production")?);
+            }
+        }
+
+        if let Some(events) = frame.get("events").and_then(Value::as_array) {
+            for event in events {
+                if field(event, "type")?.as_str() != Some("construct") {
+                    continue;
+                }
+                let owner = PlayerId(number(event, "owner_id")?);
+                if owner.0 >= self.halite.len() {
+                    return Err(HltError::UnknownPlayer(owner.0));
+                }
+                let id = DropoffId(number(event, "id")?);
+                let position = position(field(event, "location")?)?;
+                self.dropoffs.insert(id, Dropoff { owner, id, position });
+            }
+        }
+
+        if let Some(energy) = frame.get("energy").and_then(Value::as_object) {
+            for (player_id, halite) in energy {
+                let player_id = parse_id(player_id)?;
+                let player_halite = self.halite.get_mut(player_id).ok_or(HltError::UnknownPlayer(player_id))?;
+                *player_halite = halite.as_u64().ok_or_else(|| invalid("energy isn't a number"))? as usize;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reuses the wire format parser, so replays accept the same commands as the engine.
+fn parse_command(command: &Value) -> Result<Command, HltError> {
+    let wire = match field(command, "type")?.as_str() {
+        Some("g") => "g".to_string(),
+        Some("c") => format!("c {}", number(command, "id")?),
+        Some("m") => {
+            let direction = field(command, "direction")?.as_str().ok_or_else(|| invalid("direction isn't a string"))?;
+            format!("m {} {}", number(command, "id")?, direction)
+        }
+        _ => return Err(HltError::InvalidCommand(command.to_string())),
+    };
+    wire.parse()
+}
+
+fn invalid(message: &str) -> HltError {
+    HltError::InvalidReplay(message.to_string())
+}
+
+fn field<'v>(value: &'v Value, key: &str) -> Result<&'v Value, HltError> {
+    value.get(key).ok_or_else(|| HltError::InvalidReplay(format!("missing '{}'", key)))
+}
+
+fn number(value: &Value, key: &str) -> Result<usize, HltError> {
+    field(value, key)?.as_u64()
+        .map(|number| number as usize)
+        .ok_or_else(|| HltError::InvalidReplay(format!("'{}' isn't a number", key)))
+}
+
+fn array<'v>(value: &'v Value, key: &str) -> Result<&'v Vec<Value>, HltError> {
+    field(value, key)?.as_array().ok_or_else(|| HltError::InvalidReplay(format!("'{}' isn't an array", key)))
+}
+
+fn object(value: &Value) -> Result<&Map<String, Value>, HltError> {
+    value.as_object().ok_or_else(|| invalid("expected an object"))
+}
+
+fn position(value: &Value) -> Result<Position, HltError> {
+    Ok(Position { x: number(value, "x")? as i32, y: number(value, "y")? as i32 })
+}
+
+/// Ids are the keys of JSON objects, so they are strings.
+fn parse_id(key: &str) -> Result<usize, HltError> {
+    key.parse().map_err(|_| HltError::InvalidNumber(key.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::direction::Direction;
+    use simulator;
+    use simulator::replay::Replay;
+    use simulator::Simulator;
+    use std::io::BufRead;
+
+    type Snapshot = (usize, Vec<usize>, Vec<(usize, usize, Position, usize)>, Vec<usize>);
+
+    /// The parts of the state that both the simulator and the replay know.
+    fn snapshot<R: BufRead>(game: &Game<R>) -> Snapshot {
+        let halite = game.players.iter().map(|player| player.halite).collect();
+        let mut ships: Vec<(usize, usize, Position, usize)> = game.ships.values()
+            .map(|ship| (ship.id.0, ship.owner.0, ship.position, ship.halite))
+            .collect();
+        ships.sort_by_key(|ship| ship.0);
+        let cells = game.game_map.indices().map(|index| game.game_map.cell(index).halite).collect();
+        (game.turn_number, halite, ships, cells)
+    }
+
+    /// Spawns now and then, and moves every ship around, sometimes staying to mine.
+    fn wander<R: BufRead>(game: &Game<R>) -> Vec<Command> {
+        const DIRECTIONS: [Direction; 5] =
+            [Direction::North, Direction::East, Direction::Still, Direction::South, Direction::West];
+        let me = &game.players[game.my_id.0];
+        let mut commands: Vec<Command> = me.ship_ids.iter()
+            .map(|&ship_id| Command::Move(ship_id, DIRECTIONS[(ship_id.0 + game.turn_number / 2) % 5]))
+            .collect();
+        if game.turn_number % 4 == 1 && me.halite >= game.constants.ship_cost {
+            commands.push(Command::Spawn);
+        }
+        commands
+    }
+
+    #[test]
+    fn a_simulated_game_reads_back_the_same() {
+        let mut constants = simulator::default_constants(16, 16);
+        constants.max_turns = 40;
+        let mut simulator = Simulator::new(constants, 2, 16, 16, 7);
+        let mut replay = Replay::new(&simulator, &["first", "second"]);
+
+        let mut seen: Vec<Snapshot> = Vec::new();
+        let mut sent: Vec<Vec<Vec<Command>>> = Vec::new();
+        simulator.play_out(Some(&mut replay), |simulator, player_id| {
+            let game = simulator.game_for(player_id);
+            if player_id.0 == 0 {
+                seen.push(snapshot(&game));
+                sent.push(Vec::new());
+            }
+            let commands = wander(&game);
+            sent.last_mut().expect("Player 0 goes first.").push(commands.clone());
+            commands
+        });
+        assert!(simulator.eliminations.iter().all(Option::is_none));
+        // Ships should have been spawned and some of them collided.
+        let ship_counts: Vec<usize> = seen.iter().map(|snapshot| snapshot.2.len()).collect();
+        let most_ships = ship_counts.iter().cloned().max().unwrap_or(0);
+        assert!(most_ships >= 4 && ship_counts[ship_counts.len() - 1] < most_ships, "{:?}", ship_counts);
+
+        for &compressed in &[false, true] {
+            let mut bytes = Vec::new();
+            replay.write(&mut bytes, compressed).unwrap();
+            let reader = ReplayReader::from_bytes(&bytes).unwrap();
+            assert_eq!(reader.player_names, vec!["first", "second"]);
+            assert_eq!(reader.number_of_turns(), seen.len());
+
+            let turns: Vec<ReplayTurn> = reader.turns(PlayerId(0)).unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(turns.len(), seen.len());
+            for ((turn, expected), moves) in turns.iter().zip(&seen).zip(&sent) {
+                assert_eq!(snapshot(&turn.game), *expected, "turn {}", expected.0);
+                assert_eq!(turn.moves, *moves, "turn {}", expected.0);
+            }
+        }
+    }
+}
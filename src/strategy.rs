@@ -0,0 +1,91 @@
+use example_bot::ExampleBot;
+use fixed_pattern_bot::FixedPatternBot;
+use hlt::command::Command;
+use hlt::command_queue::CommandQueue;
+use hlt::error::HltError;
+use hlt::game::Game;
+use move_random_and_back::MoveRandomAndBack;
+use rand::SeedableRng;
+use rand::XorShiftRng;
+use std::io::BufRead;
+use std::io::StdinLock;
+
+/// A bot that decides the commands of my player every turn.
+/// By default it plays against the engine on stdin, but it can
+/// just as well be fed a `Game` from somewhere else.
+pub trait Strategy<R: BufRead = StdinLock<'static>> {
+    /// Called once before the bot says it's ready, so there is
+    /// no time limit yet. The game has no ships at that point.
+    fn init(&mut self, _game: &Game<R>) {}
+
+    /// The commands for the current turn.
+    fn next_turn(&mut self, game: &Game<R>) -> Vec<Command>;
+}
+
+/// The names `by_name` knows, the first one is the default.
+pub const STRATEGY_NAMES: [&str; 3] = ["move_random_and_back", "fixed_pattern", "example"];
+
+/// All randomness of a bot comes from this, so the same seed
+/// on the same map always gives the same commands.
+pub fn seeded_rng(seed: u64) -> XorShiftRng {
+    // SplitMix64 spreads every bit of the seed over the whole state,
+    // so neighbouring seeds don't start with similar numbers.
+    let mut state = seed;
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_mut(8) {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        chunk.copy_from_slice(&z.to_le_bytes());
+    }
+    XorShiftRng::from_seed(bytes)
+}
+
+pub fn by_name<R: BufRead + 'static>(name: &str, rng_seed: u64) -> Option<Box<dyn Strategy<R>>> {
+    match name {
+        "move_random_and_back" => Some(Box::new(MoveRandomAndBack::new(rng_seed))),
+        "fixed_pattern" => Some(Box::new(FixedPatternBot::new())),
+        "example" => Some(Box::new(ExampleBot::new(rng_seed))),
+        _ => None,
+    }
+}
+
+/// Plays against the engine on stdin/stdout until it closes the connection.
+pub fn play(strategy: &mut dyn Strategy, game: &mut Game) -> Result<(), HltError> {
+    loop {
+        game.update_frame()?;
+        let commands = strategy.next_turn(game);
+
+        // The engine would kill the bot for these, so they are left out.
+        let (commands, errors) = CommandQueue::without_invalid(
+            &commands, &game.players[game.my_id.0], &game.ships, &game.game_map, &game.constants);
+        for error in errors {
+            game.log.borrow_mut().log(&format!("Dropped invalid command: {}", error));
+        }
+
+        Game::end_turn(&commands)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn every_bit_of_the_seed_counts() {
+        let first_numbers = |seed: u64| -> Vec<u32> {
+            let mut rng = seeded_rng(seed);
+            (0..4).map(|_| rng.gen()).collect()
+        };
+        let seeds: Vec<u64> = (0..64).map(|bit| 1 << bit).chain(Some(0)).collect();
+        for (i, &a) in seeds.iter().enumerate() {
+            for &b in &seeds[i + 1..] {
+                assert_ne!(first_numbers(a), first_numbers(b), "seeds {} and {}", a, b);
+            }
+        }
+        assert_eq!(first_numbers(42), first_numbers(42));
+    }
+}
@@ -0,0 +1,202 @@
+use hlt::direction::Direction;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::player::Player;
+use hlt::PlayerId;
+use hlt::position::Position;
+use std::io::BufRead;
+
+/// How the map is mirrored between the players.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SymmetryKind {
+    /// No opponent has a mirrored side, e.g. a single player.
+    None,
+    /// The left and right half are mirrored (2 players).
+    Horizontal,
+    /// The top and bottom half are mirrored.
+    Vertical,
+    /// Mirrored both ways (4 players).
+    Both,
+}
+
+/// A reflection that maps my side of the map to the side of one player.
+/// `x` becomes `axis_x - x`, wrapped around the map, and the same for `y`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Mirror {
+    pub axis_x: Option<i32>,
+    pub axis_y: Option<i32>,
+}
+
+impl Mirror {
+    pub fn apply(&self, game_map: &GameMap, position: &Position) -> Position {
+        let x = self.axis_x.map_or(position.x, |axis| axis - position.x);
+        let y = self.axis_y.map_or(position.y, |axis| axis - position.y);
+        game_map.normalize(&Position { x, y })
+    }
+
+    /// The direction the other player would take for the same move.
+    pub fn apply_to_direction(&self, direction: Direction) -> Direction {
+        match direction {
+            Direction::East | Direction::West if self.axis_x.is_some() => direction.invert_direction(),
+            Direction::North | Direction::South if self.axis_y.is_some() => direction.invert_direction(),
+            _ => direction,
+        }
+    }
+}
+
+/// Finds out how the map is mirrored from the initial halite and the shipyards.
+pub struct Symmetry {
+    pub kind: SymmetryKind,
+    /// Indexed by player id. Mine is the identity,
+    /// None for players whose side isn't a mirror of mine.
+    mirrors: Vec<Option<Mirror>>,
+}
+
+impl Symmetry {
+    /// Only works with the map before the first turn, because mining breaks the symmetry.
+    pub fn detect(game_map: &GameMap, players: &[Player], my_id: PlayerId) -> Symmetry {
+        let my_shipyard = players[my_id.0].shipyard.position;
+
+        let mirrors: Vec<Option<Mirror>> = players.iter()
+            .map(|player| {
+                if player.id == my_id {
+                    return Some(Mirror { axis_x: None, axis_y: None });
+                }
+                let shipyard = player.shipyard.position;
+                let axis_x = my_shipyard.x + shipyard.x;
+                let axis_y = my_shipyard.y + shipyard.y;
+                let candidates = [
+                    Mirror { axis_x: Some(axis_x), axis_y: None },
+                    Mirror { axis_x: None, axis_y: Some(axis_y) },
+                    Mirror { axis_x: Some(axis_x), axis_y: Some(axis_y) },
+                ];
+                candidates.iter()
+                    .find(|mirror| mirror.apply(game_map, &my_shipyard) == game_map.normalize(&shipyard)
+                        && Symmetry::keeps_halite(game_map, players, mirror))
+                    .cloned()
+            })
+            .collect();
+
+        let flips_x = mirrors.iter().any(|mirror| mirror.is_some_and(|mirror| mirror.axis_x.is_some()));
+        let flips_y = mirrors.iter().any(|mirror| mirror.is_some_and(|mirror| mirror.axis_y.is_some()));
+        let kind = match (flips_x, flips_y) {
+            (false, false) => SymmetryKind::None,
+            (true, false) => SymmetryKind::Horizontal,
+            (false, true) => SymmetryKind::Vertical,
+            (true, true) => SymmetryKind::Both,
+        };
+        Symmetry { kind, mirrors }
+    }
+
+    /// For the initial game, e.g. in `Strategy::init`.
+    pub fn for_game<R: BufRead>(game: &Game<R>) -> Symmetry {
+        Symmetry::detect(&game.game_map, &game.players, game.my_id)
+    }
+
+    pub fn mirror(&self, player_id: PlayerId) -> Option<Mirror> {
+        self.mirrors.get(player_id.0).cloned().and_then(|mirror| mirror)
+    }
+
+    /// Where the player has the same situation as I have at the position.
+    pub fn mirror_position(&self, game_map: &GameMap, position: &Position, player_id: PlayerId) -> Option<Position> {
+        self.mirror(player_id).map(|mirror| mirror.apply(game_map, position))
+    }
+
+    /// The position for every player with a mirrored side, mine included.
+    pub fn mirrored_positions(&self, game_map: &GameMap, position: &Position) -> Vec<(PlayerId, Position)> {
+        self.mirrors.iter()
+            .enumerate()
+            .filter_map(|(index, mirror)| mirror.map(|mirror| (PlayerId(index), mirror.apply(game_map, position))))
+            .collect()
+    }
+
+    /// Shipyards start on empty cells, but with 3 players
+    /// the mirrored cell in the fourth corner isn't empty.
+    fn keeps_halite(game_map: &GameMap, players: &[Player], mirror: &Mirror) -> bool {
+        let is_shipyard = |position: &Position| players.iter()
+            .any(|player| game_map.normalize(&player.shipyard.position) == *position);
+        game_map.indices().all(|index| {
+            let position = game_map.position_of(index);
+            let mirrored = mirror.apply(game_map, &position);
+            is_shipyard(&position) || is_shipyard(&mirrored)
+                || game_map.cell(index).halite == game_map.at_position(&mirrored).halite
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::shipyard::Shipyard;
+    use simulator;
+    use simulator::Simulator;
+
+    fn simulated(num_players: usize, seed: u64) -> Simulator {
+        Simulator::new(simulator::default_constants(32, 32), num_players, 32, 32, seed)
+    }
+
+    #[test]
+    fn generated_maps_are_detected() {
+        let expected = [
+            (1, SymmetryKind::None), (2, SymmetryKind::Horizontal), (3, SymmetryKind::Both), (4, SymmetryKind::Both)
+        ];
+        for &(num_players, kind) in &expected {
+            let simulator = simulated(num_players, num_players as u64);
+            for player in &simulator.players {
+                let symmetry = Symmetry::detect(&simulator.game_map, &simulator.players, player.id);
+                assert_eq!(symmetry.kind, kind, "{} players", num_players);
+
+                let shipyard = player.shipyard.position;
+                let mirrored = symmetry.mirrored_positions(&simulator.game_map, &shipyard);
+                assert_eq!(mirrored.len(), num_players);
+                for (other, position) in mirrored {
+                    assert_eq!(position, simulator.players[other.0].shipyard.position);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_cells_have_the_same_halite_and_moves() {
+        let simulator = simulated(4, 9);
+        let game_map = &simulator.game_map;
+        let symmetry = Symmetry::detect(game_map, &simulator.players, PlayerId(2));
+        for other in 0..4 {
+            let mirror = symmetry.mirror(PlayerId(other)).unwrap();
+            for index in game_map.indices() {
+                let position = game_map.position_of(index);
+                let mirrored = mirror.apply(game_map, &position);
+                if game_map.cell(index).structure.is_none() {
+                    assert_eq!(game_map.cell(index).halite, game_map.at_position(&mirrored).halite);
+                }
+                assert_eq!(mirror.apply(game_map, &mirrored), position, "Mirrors undo themselves.");
+                for direction in Direction::get_all_cardinals() {
+                    assert_eq!(
+                        mirror.apply(game_map, &position.directional_offset(direction)),
+                        game_map.normalize(&mirrored.directional_offset(mirror.apply_to_direction(direction))));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unrelated_sides_have_no_mirror() {
+        let halite: Vec<usize> = (0..64).map(|cell| cell * 7 % 23).collect();
+        let game_map = GameMap::new(8, 8, &halite);
+        let players: Vec<Player> = [Position { x: 1, y: 2 }, Position { x: 6, y: 2 }].iter()
+            .enumerate()
+            .map(|(index, &position)| Player {
+                id: PlayerId(index),
+                shipyard: Shipyard { owner: PlayerId(index), position },
+                halite: 5000,
+                ship_ids: Vec::new(),
+                dropoff_ids: Vec::new(),
+            })
+            .collect();
+        let symmetry = Symmetry::detect(&game_map, &players, PlayerId(0));
+        assert_eq!(symmetry.kind, SymmetryKind::None);
+        assert!(symmetry.mirror(PlayerId(0)).is_some());
+        assert_eq!(symmetry.mirror(PlayerId(1)), None);
+        assert_eq!(symmetry.mirror_position(&game_map, &Position { x: 3, y: 3 }, PlayerId(1)), None);
+    }
+}
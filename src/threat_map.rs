@@ -0,0 +1,232 @@
+use distance_field::DistanceField;
+use hlt::game::Game;
+use hlt::direction::Direction;
+use hlt::game_map::CellIndex;
+use hlt::game_map::GameMap;
+use hlt::map_cell::Structure;
+use hlt::PlayerId;
+use hlt::ship::Ship;
+use hlt::ShipId;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// An enemy ship with at least `max_halite / RETURNING_CARGO_DIVISOR` is expected to
+/// return to its nearest structure. Bots usually send their ships home somewhere
+/// between half full and full, so from half a cargo on, that is the better guess.
+const RETURNING_CARGO_DIVISOR: usize = 2;
+
+/// How dangerous it is for one of my ships to end the next turn on a cell.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Safety {
+    /// No enemy ship can get there.
+    Safe,
+    /// An enemy ship can get there, but a collision wouldn't cost me more than the enemy.
+    Risky,
+    /// A collision would cost me more than the enemy.
+    Never,
+}
+
+/// Who collects the cargo that is dropped on a cell.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Collector {
+    Me,
+    Enemies,
+    Nobody,
+}
+
+/// Every cell an enemy ship could be on after the next turn: the cell it is on and,
+/// if it can pay for moving, its neighbours. When two ships collide, both sink and
+/// their cargo is dropped on the cell. Whoever has a shipyard or dropoff closer by
+/// probably collects it, on a shipyard or dropoff it goes to the owner right away.
+/// With more than two players, losing a ship is a loss of its own, because
+/// the players that weren't involved come out ahead.
+pub struct ThreatMap {
+    /// The enemy ships that could get there, indexed by `CellIndex`.
+    threats: Vec<Vec<ShipId>>,
+    cargo: HashMap<ShipId, usize>,
+    /// Indexed by `CellIndex`.
+    collectors: Vec<Collector>,
+    enemy_structures: DistanceField,
+    /// Enemy ships with that much cargo are expected to return.
+    returning_cargo: usize,
+    /// What losing a ship in a collision costs me on top of the cargo.
+    ship_loss: i64,
+}
+
+impl ThreatMap {
+    pub fn new<R: BufRead>(game: &Game<R>, player_id: PlayerId) -> ThreatMap {
+        let game_map = &game.game_map;
+        let mut threats = vec![Vec::new(); game_map.width * game_map.height];
+        let mut cargo = HashMap::new();
+        for ship in game.ships.values().filter(|ship| ship.owner != player_id) {
+            let index = game_map.index_of(&ship.position);
+            threats[index.0].push(ship.id);
+            if game_map.cell(index).halite / game.constants.move_cost_ratio <= ship.halite {
+                for neighbour in &game_map.neighbours(index) {
+                    threats[neighbour.0].push(ship.id);
+                }
+            }
+            cargo.insert(ship.id, ship.halite);
+        }
+
+        let mine = DistanceField::to_structures(game, player_id, |_| false);
+        let theirs = DistanceField::to_enemy_structures(game, player_id, |_| false);
+        let collectors = game_map.indices()
+            .map(|index| match game_map.cell(index).structure {
+                Structure::Shipyard(owner) if owner == player_id => Collector::Me,
+                Structure::Dropoff(dropoff_id) if game.dropoffs[&dropoff_id].owner == player_id => Collector::Me,
+                Structure::Shipyard(_) | Structure::Dropoff(_) => Collector::Enemies,
+                Structure::None => match (mine.distance(index), theirs.distance(index)) {
+                    (Some(mine), Some(theirs)) if mine < theirs => Collector::Me,
+                    (Some(mine), Some(theirs)) if mine > theirs => Collector::Enemies,
+                    (Some(_), None) => Collector::Me,
+                    (None, Some(_)) => Collector::Enemies,
+                    _ => Collector::Nobody,
+                },
+            })
+            .collect();
+
+        ThreatMap {
+            threats,
+            cargo,
+            collectors,
+            enemy_structures: theirs,
+            returning_cargo: game.constants.max_halite / RETURNING_CARGO_DIVISOR,
+            ship_loss: if game.players.len() > 2 { game.constants.ship_cost as i64 } else { 0 },
+        }
+    }
+
+    /// As seen by my player.
+    pub fn for_me<R: BufRead>(game: &Game<R>) -> ThreatMap {
+        ThreatMap::new(game, game.my_id)
+    }
+
+    /// What the enemy loses minus what I lose, if my ship with `my_cargo` collides
+    /// on the cell with the enemy ship that is worst for me. None if no enemy ship can get there.
+    pub fn collision_value(&self, index: CellIndex, my_cargo: usize) -> Option<i64> {
+        self.threats[index.0].iter()
+            .map(|ship_id| self.swing(index, my_cargo, self.cargo[ship_id]))
+            .min()
+    }
+
+    /// What the enemy loses minus what I lose when two ships collide on the cell.
+    pub fn swing(&self, index: CellIndex, my_cargo: usize, their_cargo: usize) -> i64 {
+        let (my_cargo, their_cargo) = (my_cargo as i64, their_cargo as i64);
+        let cargo = match self.collectors[index.0] {
+            Collector::Me => 2 * their_cargo,
+            Collector::Enemies => -2 * my_cargo,
+            Collector::Nobody => their_cargo - my_cargo,
+        };
+        cargo - self.ship_loss
+    }
+
+    /// Where an enemy ship will probably be in the next `turns` turns. With at least
+    /// `returning_cargo`, it returns to its nearest structure, otherwise it stays and mines.
+    pub fn predicted_path(&self, game_map: &GameMap, ship: &Ship, turns: usize) -> Vec<CellIndex> {
+        let mut index = game_map.index_of(&ship.position);
+        (0..turns)
+            .map(|_| {
+                if ship.halite >= self.returning_cargo {
+                    let direction = self.enemy_structures.step_towards_source(game_map, index)
+                        .unwrap_or(Direction::Still);
+                    index = game_map.neighbour(index, direction);
+                }
+                index
+            })
+            .collect()
+    }
+
+    pub fn safety(&self, index: CellIndex, my_cargo: usize) -> Safety {
+        match self.collision_value(index, my_cargo) {
+            None => Safety::Safe,
+            Some(value) if value >= 0 => Safety::Risky,
+            Some(_) => Safety::Never,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hlt::player::Player;
+    use hlt::position::Position;
+    use hlt::shipyard::Shipyard;
+    use std::io;
+    use std::io::Empty;
+
+    const ENEMY: ShipId = ShipId(7);
+
+    fn player(id: usize, x: i32, y: i32) -> Player {
+        Player {
+            id: PlayerId(id),
+            shipyard: Shipyard { owner: PlayerId(id), position: Position { x, y } },
+            halite: 5000,
+            ship_ids: Vec::new(),
+            dropoff_ids: Vec::new(),
+        }
+    }
+
+    /// My shipyard is at (2, 4), player 1 has one at (13, 4) and a ship on (8, 4).
+    fn game(num_players: usize, enemy_cargo: usize, halite_below_enemy: usize) -> Game<Empty> {
+        let mut halite = vec![0; 16 * 16];
+        halite[4 * 16 + 8] = halite_below_enemy;
+        let players = vec![player(0, 2, 4), player(1, 13, 4), player(2, 8, 12)];
+        let constants = ::simulator::default_constants(16, 16);
+        let max_halite = constants.max_halite;
+        let mut game = Game::from_state(io::empty(), constants, PlayerId(0),
+            players.into_iter().take(num_players).collect(), GameMap::new(16, 16, &halite));
+        game.ships.insert(ENEMY, Ship::new(PlayerId(1), ENEMY, Position { x: 8, y: 4 }, enemy_cargo, max_halite));
+        game.players[1].ship_ids.push(ENEMY);
+        game.mark_entities();
+        game
+    }
+
+    fn at(game: &Game<Empty>, x: i32, y: i32) -> CellIndex {
+        game.game_map.index_of(&Position { x, y })
+    }
+
+    #[test]
+    fn cells_out_of_reach_are_safe() {
+        let game = game(2, 500, 0);
+        let threats = ThreatMap::for_me(&game);
+        for &(x, y) in &[(6, 4), (8, 2), (10, 4), (9, 5), (2, 4)] {
+            assert_eq!(threats.safety(at(&game, x, y), 900), Safety::Safe, "({}, {})", x, y);
+        }
+        for &(x, y) in &[(8, 4), (7, 4), (9, 4), (8, 3), (8, 5)] {
+            assert_ne!(threats.safety(at(&game, x, y), 0), Safety::Safe, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn a_ship_that_cant_pay_for_moving_only_threatens_its_cell() {
+        let game = game(2, 50, 1000);
+        let threats = ThreatMap::for_me(&game);
+        assert_ne!(threats.safety(at(&game, 8, 4), 0), Safety::Safe);
+        assert_eq!(threats.safety(at(&game, 7, 4), 0), Safety::Safe);
+        assert_eq!(threats.safety(at(&game, 8, 5), 0), Safety::Safe);
+    }
+
+    #[test]
+    fn the_cargo_goes_to_the_closer_structure() {
+        let game = game(2, 500, 0);
+        let threats = ThreatMap::for_me(&game);
+        // Closer to my shipyard, I collect both cargos.
+        assert_eq!(threats.safety(at(&game, 7, 4), 900), Safety::Risky);
+        // Closer to theirs, an empty ship loses nothing, a loaded one its cargo.
+        assert_eq!(threats.safety(at(&game, 9, 4), 0), Safety::Risky);
+        assert_eq!(threats.safety(at(&game, 9, 4), 100), Safety::Never);
+        assert_eq!(threats.collision_value(at(&game, 9, 4), 100), Some(-200));
+        assert_eq!(threats.collision_value(at(&game, 7, 4), 100), Some(1000));
+        assert_eq!(threats.collision_value(at(&game, 0, 0), 100), None);
+    }
+
+    #[test]
+    fn with_more_players_the_ship_counts_too() {
+        let game = game(3, 400, 0);
+        let threats = ThreatMap::for_me(&game);
+        let ship_cost = game.constants.ship_cost as i64;
+        assert_eq!(threats.collision_value(at(&game, 7, 4), 0), Some(800 - ship_cost));
+        assert_eq!(threats.safety(at(&game, 7, 4), 0), Safety::Never);
+        assert_eq!(ThreatMap::for_me(&self::game(2, 400, 0)).safety(at(&game, 7, 4), 0), Safety::Risky);
+    }
+}
@@ -0,0 +1,44 @@
+extern crate my_bot;
+
+use my_bot::hlt::PlayerId;
+use my_bot::simulator;
+use my_bot::simulator::Simulator;
+use my_bot::strategy;
+use my_bot::strategy::Strategy;
+use std::io::Empty;
+
+/// Plays a short game of the default strategy against the opponent, through the library
+/// like the `simulate` binary does.
+fn play(opponent: &str, seed: u64) -> Simulator {
+    let mut constants = simulator::default_constants(16, 16);
+    constants.max_turns = 80;
+    let mut simulator = Simulator::new(constants, 2, 16, 16, seed);
+    let mut strategies: Vec<Box<dyn Strategy<Empty>>> = vec![
+        strategy::by_name(strategy::STRATEGY_NAMES[0], seed).unwrap(),
+        strategy::by_name(opponent, seed + 1).unwrap(),
+    ];
+    for (index, strategy) in strategies.iter_mut().enumerate() {
+        strategy.init(&simulator.game_for(PlayerId(index)));
+    }
+    simulator.play_out(None, |simulator, player_id| {
+        strategies[player_id.0].next_turn(&simulator.game_for(player_id))
+    });
+    simulator
+}
+
+#[test]
+fn every_strategy_plays_valid_commands() {
+    for name in &strategy::STRATEGY_NAMES {
+        let simulator = play(name, 3);
+        assert_eq!(simulator.eliminations, vec![None, None], "{}", name);
+        assert!(simulator.turn_number > simulator.constants.max_turns, "{}", name);
+    }
+}
+
+#[test]
+fn the_same_seed_plays_the_same_game() {
+    let halite = |simulator: Simulator| -> Vec<usize> {
+        simulator.players.iter().map(|player| player.halite).collect()
+    };
+    assert_eq!(halite(play(strategy::STRATEGY_NAMES[0], 5)), halite(play(strategy::STRATEGY_NAMES[0], 5)));
+}